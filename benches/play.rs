@@ -0,0 +1,129 @@
+//! Compares playing every answer through the `String`-based `Guesser` API with playing it through
+//! the `WordId`-based `IdGuesser` API, both in time and in heap allocations per game. Playing from
+//! a recorded tree of the solver's guesses, looking two guesses ahead, ranking by the probability
+//! of winning within six guesses, and Jotto, played through `Wordle::play_scored`, are measured as
+//! well.
+//!
+//! Run with `cargo bench --bench play`, optionally followed by `-- N` to only play the first `N`
+//! answers.
+
+use roget::{GameConfig, GameResult, JottoFeedback, Options, Rank, TreeGuesser, Wordle};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Forwards to the system allocator, counting every allocation along the way.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL_ALLOC: Counting = Counting;
+
+fn bench(
+    name: &str,
+    answers: &[&'static str],
+    mut play: impl FnMut(&'static str) -> Option<usize>,
+) -> f64 {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut score = 0;
+    let mut failed = 0;
+    for &answer in answers {
+        match play(answer) {
+            Some(s) => score += s,
+            None => failed += 1,
+        }
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let games = answers.len();
+    let won = games - failed;
+    println!(
+        "{:>6}: {:>10.2?}/game, {:>7.1} allocations/game (average score: {:.4}, failure rate: {:.2}%)",
+        name,
+        elapsed / games as u32,
+        allocations as f64 / games as f64,
+        score as f64 / won as f64,
+        100.0 * failed as f64 / games as f64
+    );
+    score as f64 / won as f64
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` along, so we skip anything that isn't a number.
+    let games = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(usize::MAX);
+    let options = Options::builtin().expect("the benchmarks play the built-in dictionary");
+    let w = Wordle::with_dictionary(options.dictionary);
+    let answers = w.dictionary().answers();
+    let answers = &answers[..games.min(answers.len())];
+
+    // The first games fill in the solver's correctness cache, which would skew whichever API gets
+    // benchmarked first.
+    for &answer in answers {
+        w.play_ids(answer, options.build());
+    }
+
+    bench("String", answers, |answer| {
+        w.play(answer, options.build()).score()
+    });
+    let one = bench("WordId", answers, |answer| {
+        w.play_ids(answer, options.build()).score()
+    });
+    let tree = w.record_strategy(|| options.build());
+    bench("Tree", answers, |answer| {
+        w.play_ids(answer, TreeGuesser::new(w.dictionary(), &tree))
+            .score()
+    });
+
+    let mut ahead = options;
+    ahead.rank_by = Rank::ExpectedScore;
+    ahead.lookahead.depth = 2;
+    let two = bench("Depth2", answers, |answer| {
+        w.play_ids(answer, ahead.build()).score()
+    });
+    println!(
+        "Looking two guesses ahead improves the average score by {:.4}",
+        one - two
+    );
+
+    let mut streak = options;
+    streak.rank_by = Rank::WinProbability;
+    bench("WinP", answers, |answer| {
+        w.play_ids(answer, streak.build()).score()
+    });
+
+    // Jotto games take many more guesses, so these numbers aren't comparable to the ones above.
+    let mut config = GameConfig::default();
+    config.max_guesses = 32;
+    let w = w.with_config(config);
+    let jotto = options.with_feedback::<JottoFeedback>();
+    for &answer in answers {
+        let _ = w.play_scored(answer, jotto.build());
+    }
+    bench("Jotto", answers, |answer| {
+        match w.play_scored(answer, jotto.build()) {
+            Ok(GameResult::Won(s)) => Some(s),
+            _ => None,
+        }
+    });
+}
@@ -0,0 +1,50 @@
+//! Plays Xordle with `XordleSolver` over random pairs of answers that share no letters, and reports
+//! the time per game and the average score.
+//!
+//! Run with `cargo bench --bench xordle`, optionally followed by `-- N` to play `N` pairs instead
+//! of the default 100. The pairs are drawn from a fixed seed, so runs are comparable.
+
+use roget::{GameConfig, GameResult, Options, Rng, Wordle};
+use std::time::Instant;
+
+fn main() {
+    // `cargo bench` passes `--bench` along, so we skip anything that isn't a number.
+    let games = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(100);
+    let options = Options::builtin().expect("the benchmark plays the built-in dictionary");
+    // Plenty of guesses, so that the score distribution isn't chopped off.
+    let mut config = GameConfig::default();
+    config.max_guesses = 32;
+    let w = Wordle::with_dictionary(options.dictionary).with_config(config);
+    let answers = w.dictionary().answers();
+
+    let mut rng = Rng::new(0x5eed);
+    let mut pairs = Vec::with_capacity(games);
+    while pairs.len() < games {
+        let a = answers[rng.below(answers.len())];
+        let b = answers[rng.below(answers.len())];
+        if !a.chars().any(|c| b.contains(c)) {
+            pairs.push([a, b]);
+        }
+    }
+
+    let start = Instant::now();
+    let mut score = 0;
+    let mut failed = 0;
+    for &pair in &pairs {
+        match w.play_xordle(pair, options.build_xordle()) {
+            Ok(GameResult::Won(s)) => score += s,
+            _ => failed += 1,
+        }
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "Xordle: {:>10.2?}/game, {} of {} pairs failed (average score: {:.4})",
+        elapsed / games as u32,
+        failed,
+        games,
+        score as f64 / (games - failed) as f64
+    );
+}
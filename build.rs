@@ -1,31 +1,89 @@
 use std::io::prelude::*;
 
-// change to 5 or 6
-const DICTIONARY: &str = include_str!("dictionary-5.txt");
+// Keep in sync with `MIN_WORD_LENGTH` and `MAX_WORD_LENGTH` in `src/lib.rs`.
+const LENGTHS: std::ops::RangeInclusive<usize> = 4..=11;
 
 fn main() {
-    println!("cargo:rerun-if-changed=dictionary.txt");
+    let manifest_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
     let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
     let mut f = std::fs::File::create(out_dir.join("dictionary.rs"))
         .expect("could not create file in OUT_DIR");
 
-    let mut words = Vec::from_iter(DICTIONARY.lines().map(|line| {
-        let (word, count) = line
-            .split_once(' ')
-            .expect("every line is word + space + frequency");
-        let count: usize = count.parse().expect("every count is a number");
-        (word, count)
-    }));
-    words.sort_unstable_by_key(|&(_, count)| std::cmp::Reverse(count));
+    // Every word length that has both a `dictionary-N.txt` and an `answers-N.txt` gets baked in.
+    let mut builtin = Vec::new();
+    for length in LENGTHS {
+        let dictionary = manifest_dir.join(format!("dictionary-{}.txt", length));
+        let answers = manifest_dir.join(format!("answers-{}.txt", length));
+        println!("cargo:rerun-if-changed={}", dictionary.display());
+        println!("cargo:rerun-if-changed={}", answers.display());
+        let (Ok(dictionary), Ok(answers)) = (
+            std::fs::read_to_string(&dictionary),
+            std::fs::read_to_string(&answers),
+        ) else {
+            continue;
+        };
+
+        let mut words = Vec::from_iter(dictionary.lines().map(|line| {
+            let (word, count) = line
+                .split_once(' ')
+                .expect("every line is word + space + frequency");
+            let count: usize = count.parse().expect("every count is a number");
+            assert_eq!(
+                word.chars().count(),
+                length,
+                "word '{}' in dictionary-{}.txt has the wrong length",
+                word,
+                length
+            );
+            (word, count)
+        }));
+        words.sort_unstable_by_key(|&(_, count)| std::cmp::Reverse(count));
+        let mut letters = Vec::from_iter(words.iter().flat_map(|(word, _)| word.chars()));
+        letters.sort_unstable();
+        letters.dedup();
+        // Keep in sync with `MAX_ALPHABET_SIZE` in `src/alphabet.rs`.
+        assert!(
+            letters.len() <= 64,
+            "dictionary-{}.txt uses more than 64 distinct letters",
+            length
+        );
+
+        writeln!(
+            f,
+            "static DICTIONARY_{}: [(&str, usize); {}] = [",
+            length,
+            words.len()
+        )
+        .unwrap();
+        for (word, count) in words {
+            writeln!(f, "(\"{}\", {}),", word, count).unwrap();
+        }
+        writeln!(f, "];").unwrap();
+
+        let answers = Vec::from_iter(answers.split_whitespace());
+        writeln!(
+            f,
+            "static ANSWERS_{}: [&str; {}] = [",
+            length,
+            answers.len()
+        )
+        .unwrap();
+        for answer in answers {
+            writeln!(f, "\"{}\",", answer).unwrap();
+        }
+        writeln!(f, "];").unwrap();
+
+        builtin.push(length);
+    }
 
     writeln!(
         f,
-        "pub const DICTIONARY: [(&str, usize); {}] = [",
-        words.len()
+        "static BUILTIN: [(usize, &[(&str, usize)], &[&str]); {}] = [",
+        builtin.len()
     )
     .unwrap();
-    for (word, count) in words {
-        writeln!(f, "(\"{}\", {}),", word, count).unwrap();
+    for length in builtin {
+        writeln!(f, "({0}, &DICTIONARY_{0}, &ANSWERS_{0}),", length).unwrap();
     }
     write!(f, "];").unwrap();
 }
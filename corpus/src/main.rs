@@ -1,15 +1,22 @@
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::num::NonZeroUsize;
 
-// change to 5 or 6
-pub const WORD_LENGTH: usize = 5;
-const DICTIONARY: &str = include_str!("../wordle-5.txt");
-
 fn main() {
-    let files: Vec<_> = std::env::args().skip(1).collect();
+    let mut args = std::env::args().skip(1);
+    let dictionary = args
+        .next()
+        .expect("usage: corpus <word list> <ngram files...>");
+    let dictionary = std::fs::read_to_string(&dictionary)
+        .unwrap_or_else(|e| panic!("could not open word list '{}': {}", dictionary, e));
+    let dictionary = &dictionary;
+    // The word list may mix word lengths, so only look up ngrams of lengths it actually contains.
+    let word_lengths: HashSet<_> = dictionary.lines().map(|w| w.len()).collect();
+    let word_lengths = &word_lengths;
+
+    let files: Vec<_> = args.collect();
     let words: HashMap<_, _> = files
         .into_par_iter()
         .map(|file| {
@@ -18,7 +25,7 @@ fn main() {
             let file = BufReader::new(file);
             let file = flate2::bufread::GzDecoder::new(file);
             let mut file = BufReader::new(file);
-            let mut words: HashMap<_, _> = DICTIONARY.lines().map(|w| (w.as_bytes(), 0)).collect();
+            let mut words: HashMap<_, _> = dictionary.lines().map(|w| (w.as_bytes(), 0)).collect();
             let mut line = Vec::new();
             loop {
                 line.clear();
@@ -36,15 +43,25 @@ fn main() {
                 } else {
                     word
                 };
-                if word.len() != WORD_LENGTH {
+                if !word_lengths.contains(&word.len()) {
                     line.clear();
                     continue;
                 }
-                if !word.iter().all(|c| matches!(c, b'a'..=b'z' | b'A'..=b'Z')) {
-                    continue;
-                }
-                word.make_ascii_lowercase();
-                if let Some(accum) = words.get_mut(&*word) {
+                let lowercase;
+                let word: &[u8] = if word.iter().all(|c| c.is_ascii_alphabetic()) {
+                    word.make_ascii_lowercase();
+                    word
+                } else {
+                    // Word lists for languages other than English also use letters outside a-z.
+                    match std::str::from_utf8(word) {
+                        Ok(w) if w.chars().all(char::is_alphabetic) => {
+                            lowercase = w.to_lowercase();
+                            lowercase.as_bytes()
+                        }
+                        _ => continue,
+                    }
+                };
+                if let Some(accum) = words.get_mut(word) {
                     let count: usize = fields
                         .map(|field| {
                             let mut columns = field.split(|&c| c == b',');
@@ -73,7 +90,7 @@ fn main() {
             map1
         });
 
-    for word in DICTIONARY.lines() {
+    for word in dictionary.lines() {
         let count = words
             .get(word.as_bytes())
             .copied()
@@ -18,6 +18,12 @@ print(inv)
 print(sqr)
 print(pow)
 
+# The distribution of the number of guesses needed, rather than just its mean, as a logistic
+# regression of whether at most k guesses were needed for each k. These give `STEPS_LEFT_FIT`.
+for (k in 1:6) {
+  print(glm((guesses <= k) ~ entropy, family = binomial, data = data))
+}
+
 p <- ggplot(data, aes(entropy, guesses)) + geom_jitter(alpha = 0.2, size = 0.7, width = 0.1, height = 0.2)
 xs <- seq(0,8,length=36)
 lin_pred <- predict(lin, newdata = data.frame(entropy = xs))
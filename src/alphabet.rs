@@ -0,0 +1,117 @@
+use crate::MAX_WORD_LENGTH;
+
+/// The most distinct letters an `Alphabet` can hold.
+pub const MAX_ALPHABET_SIZE: usize = 64;
+
+/// The set of letters that make up the words of a `Dictionary`.
+///
+/// Each letter is identified by its index into the alphabet. That index is all the feedback and
+/// matching logic looks at, so it works the same whether a letter is `a`, `ñ`, or `č`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    // Sorted, so that we can binary search for a letter's index.
+    symbols: Vec<char>,
+}
+
+impl Alphabet {
+    /// The alphabet made up of every letter used in `words`.
+    ///
+    /// Returns `None` if the words use more than `MAX_ALPHABET_SIZE` distinct letters.
+    pub fn from_words<'a>(words: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut symbols = Vec::from_iter(words.into_iter().flat_map(str::chars));
+        symbols.sort_unstable();
+        symbols.dedup();
+        if symbols.len() > MAX_ALPHABET_SIZE {
+            return None;
+        }
+        Some(Self { symbols })
+    }
+
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// The index of `letter` in this alphabet, if it's part of it.
+    pub fn index(&self, letter: char) -> Option<u8> {
+        self.symbols.binary_search(&letter).ok().map(|i| i as u8)
+    }
+
+    /// Turns `word` into a sequence of letter indices.
+    ///
+    /// Returns `None` if `word` uses letters outside this alphabet, or is too long.
+    pub fn encode(&self, word: &str) -> Option<Word> {
+        let mut encoded = Word::default();
+        for letter in word.chars() {
+            encoded.push(self.index(letter)?)?;
+        }
+        Some(encoded)
+    }
+
+    /// Turns a sequence of letter indices back into a word.
+    pub fn decode(&self, word: &Word) -> String {
+        word.iter().map(|&i| self.symbols[usize::from(i)]).collect()
+    }
+}
+
+/// A word as a sequence of letter indices into an `Alphabet`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Word {
+    len: u8,
+    // Entries past `len` are always 0 so that the derived impls only see the real letters.
+    symbols: [u8; MAX_WORD_LENGTH],
+}
+
+impl Word {
+    /// Encodes two words using a throwaway alphabet made up of just the letters in the two words.
+    ///
+    /// This lets us compare arbitrary words without first having to build an `Alphabet`.
+    ///
+    /// Returns `None` if either word is too long.
+    pub fn encode_pair(a: &str, b: &str) -> Option<(Self, Self)> {
+        if a.chars().count() > MAX_WORD_LENGTH || b.chars().count() > MAX_WORD_LENGTH {
+            return None;
+        }
+        let mut letters = ['\0'; 2 * MAX_WORD_LENGTH];
+        let mut n = 0;
+        let mut encode = |word: &str| {
+            let mut encoded = Word::default();
+            for letter in word.chars() {
+                let i = match letters[..n].iter().position(|&l| l == letter) {
+                    Some(i) => i,
+                    None => {
+                        // Can't overflow, since both words were checked to have at most
+                        // MAX_WORD_LENGTH letters each.
+                        letters[n] = letter;
+                        n += 1;
+                        n - 1
+                    }
+                };
+                encoded.push(i as u8)?;
+            }
+            Some(encoded)
+        };
+        Some((encode(a)?, encode(b)?))
+    }
+
+    fn push(&mut self, symbol: u8) -> Option<()> {
+        *self.symbols.get_mut(usize::from(self.len))? = symbol;
+        self.len += 1;
+        Some(())
+    }
+}
+
+impl std::ops::Deref for Word {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.symbols[..usize::from(self.len)]
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{Alphabet, Word, MAX_ALPHABET_SIZE, MAX_WORD_LENGTH, MIN_WORD_LENGTH};
+use once_cell::sync::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+include!(concat!(env!("OUT_DIR"), "/dictionary.rs"));
+
+/// Hard-coded opening guesses for word lengths where we've already computed one.
+static FIRST_GUESSES: &[(usize, &str)] = &[(5, "tares"), (6, "presev")];
+
+// `OnceCell` isn't `Copy`, so we need a `const` to be able to use it in an array expression.
+#[allow(clippy::declare_interior_mutable_const)]
+const UNINIT: OnceCell<Dictionary> = OnceCell::new();
+static BUILTIN_DICTIONARIES: [OnceCell<Dictionary>; MAX_WORD_LENGTH + 1] =
+    [UNINIT; MAX_WORD_LENGTH + 1];
+
+/// Identifies a word of a `Dictionary` by its position in [`Dictionary::words`].
+///
+/// A `WordId` is only meaningful for the dictionary it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WordId(u32);
+
+impl WordId {
+    pub(crate) fn from_index(idx: usize) -> Self {
+        Self(u32::try_from(idx).expect("dictionaries have fewer than 2^32 words"))
+    }
+
+    /// The position of this word in [`Dictionary::words`].
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The words allowed as guesses for a game with a particular word length, along with the list of
+/// answers the game draws from.
+pub struct Dictionary {
+    length: usize,
+    /// Every allowed word and how common it is, sorted from most to least common.
+    words: &'static [(&'static str, usize)],
+    answers: &'static [&'static str],
+    ids: HashMap<&'static str, WordId>,
+    first_guess: Option<&'static str>,
+    alphabet: Alphabet,
+    /// Every word of `words` (in the same order), encoded using `alphabet`.
+    encoded: Vec<Word>,
+
+    /// The initial set of words without any smoothing
+    pub(crate) initial_counts: OnceCell<Vec<(&'static str, f64, usize)>>,
+    /// The initial set of words after applying sigmoid smoothing.
+    pub(crate) initial_sigmoid: OnceCell<Vec<(&'static str, f64, usize)>>,
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("length", &self.length)
+            .field("words", &self.words.len())
+            .field("answers", &self.answers.len())
+            .field("alphabet", &self.alphabet.symbols())
+            .finish()
+    }
+}
+
+impl Dictionary {
+    /// Returns the dictionary baked into the binary for words of the given length, if any.
+    ///
+    /// The built-in dictionaries are read from `dictionary-N.txt` and `answers-N.txt` at build
+    /// time.
+    pub fn builtin(length: usize) -> Option<&'static Self> {
+        if !(MIN_WORD_LENGTH..=MAX_WORD_LENGTH).contains(&length) {
+            return None;
+        }
+        let &(_, words, answers) = BUILTIN.iter().find(|&&(l, _, _)| l == length)?;
+        Some(BUILTIN_DICTIONARIES[length].get_or_init(|| {
+            let first_guess = FIRST_GUESSES
+                .iter()
+                .find(|&&(l, _)| l == length)
+                .map(|&(_, word)| word);
+            let alphabet = Alphabet::from_words(words.iter().map(|&(word, _)| word))
+                .expect("build.rs checks the size of the alphabet");
+            Self::new(length, words, answers, first_guess, alphabet)
+        }))
+    }
+
+    /// The word lengths for which there is a built-in dictionary.
+    pub fn builtin_lengths() -> impl Iterator<Item = usize> {
+        BUILTIN.iter().map(|&(length, _, _)| length)
+    }
+
+    /// Loads a word list from a file in the same format as `dictionary-N.txt`: one word per line,
+    /// followed by a space and how often that word occurs.
+    ///
+    /// Every word in the list is considered a possible answer. Use [`Dictionary::load_answers`]
+    /// to restrict that.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(read_leaked(path.as_ref())?)
+    }
+
+    /// Parses a word list in the same format as `dictionary-N.txt`.
+    ///
+    /// Every word in the list is considered a possible answer.
+    pub fn parse(words: &'static str) -> io::Result<Self> {
+        let mut parsed = Vec::new();
+        for (i, line) in words.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (word, count) = line.split_once(' ').ok_or_else(|| {
+                invalid(format!(
+                    "line {}: expected a word followed by a space and its frequency",
+                    i + 1
+                ))
+            })?;
+            let count: usize = count
+                .trim()
+                .parse()
+                .map_err(|e| invalid(format!("line {}: invalid frequency: {}", i + 1, e)))?;
+            parsed.push((word, count));
+        }
+
+        let length = parsed
+            .first()
+            .ok_or_else(|| invalid("the word list is empty"))?
+            .0
+            .chars()
+            .count();
+        if !(MIN_WORD_LENGTH..=MAX_WORD_LENGTH).contains(&length) {
+            return Err(invalid(format!(
+                "words must be between {} and {} letters long, found {}",
+                MIN_WORD_LENGTH, MAX_WORD_LENGTH, length
+            )));
+        }
+        if let Some((word, _)) = parsed
+            .iter()
+            .find(|(word, _)| word.chars().count() != length)
+        {
+            return Err(invalid(format!(
+                "word '{}' is not {} letters long like the ones before it",
+                word, length
+            )));
+        }
+        let mut seen = HashSet::new();
+        if let Some((word, _)) = parsed.iter().find(|(word, _)| !seen.insert(*word)) {
+            return Err(invalid(format!("word '{}' is listed more than once", word)));
+        }
+        let alphabet =
+            Alphabet::from_words(parsed.iter().map(|&(word, _)| word)).ok_or_else(|| {
+                invalid(format!(
+                    "the words use more than {} distinct letters",
+                    MAX_ALPHABET_SIZE
+                ))
+            })?;
+        parsed.sort_unstable_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+        let words: &'static [_] = Vec::leak(parsed);
+        let answers = Vec::leak(words.iter().map(|&(word, _)| word).collect());
+        Ok(Self::new(length, words, answers, None, alphabet))
+    }
+
+    /// Returns a copy of this dictionary whose answers are read from a file in the same format as
+    /// `answers-N.txt`: whitespace-separated words.
+    pub fn load_answers(&self, path: impl AsRef<Path>) -> io::Result<Self> {
+        self.with_answers(read_leaked(path.as_ref())?)
+    }
+
+    /// Returns a copy of this dictionary whose answers are the whitespace-separated words in
+    /// `answers`.
+    pub fn with_answers(&self, answers: &'static str) -> io::Result<Self> {
+        let answers = Vec::from_iter(answers.split_whitespace());
+        if answers.is_empty() {
+            return Err(invalid("the answer list is empty"));
+        }
+        if let Some(answer) = answers.iter().find(|answer| !self.contains(answer)) {
+            return Err(invalid(format!(
+                "answer '{}' is not in the dictionary",
+                answer
+            )));
+        }
+        Ok(Self::new(
+            self.length,
+            self.words,
+            Vec::leak(answers),
+            self.first_guess,
+            self.alphabet.clone(),
+        ))
+    }
+
+    /// Moves this dictionary to the heap and leaks it, so it can be used for as long as the
+    /// program runs, just like the built-in ones.
+    pub fn leak(self) -> &'static Self {
+        Box::leak(Box::new(self))
+    }
+
+    fn new(
+        length: usize,
+        words: &'static [(&'static str, usize)],
+        answers: &'static [&'static str],
+        first_guess: Option<&'static str>,
+        alphabet: Alphabet,
+    ) -> Self {
+        let ids = HashMap::from_iter(
+            words
+                .iter()
+                .enumerate()
+                .map(|(idx, &(word, _))| (word, WordId::from_index(idx))),
+        );
+        let encoded = words
+            .iter()
+            .map(|&(word, _)| alphabet.encode(word).expect("alphabet has every letter"))
+            .collect();
+        // The hard-coded first guesses only make sense for the word lists they were computed for.
+        let first_guess = first_guess.filter(|word| ids.contains_key(word));
+        Self {
+            length,
+            words,
+            answers,
+            ids,
+            first_guess,
+            alphabet,
+            encoded,
+            initial_counts: OnceCell::new(),
+            initial_sigmoid: OnceCell::new(),
+        }
+    }
+
+    /// The length of every word in this dictionary.
+    pub fn word_length(&self) -> usize {
+        self.length
+    }
+
+    /// Every allowed word and how common it is, sorted from most to least common.
+    pub fn words(&self) -> &'static [(&'static str, usize)] {
+        self.words
+    }
+
+    /// The answers of the games to play with this dictionary.
+    pub fn answers(&self) -> &'static [&'static str] {
+        self.answers
+    }
+
+    /// The letters the words of this dictionary are made up of.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// The word at index `idx` of `words`, encoded using `alphabet`.
+    pub fn encoded(&self, idx: usize) -> &Word {
+        &self.encoded[idx]
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.ids.contains_key(word)
+    }
+
+    /// The `WordId` of `word`, if it is in this dictionary.
+    pub fn id(&self, word: &str) -> Option<WordId> {
+        self.ids.get(word).copied()
+    }
+
+    /// The word identified by `id`.
+    pub fn word(&self, id: WordId) -> &'static str {
+        self.words[id.index()].0
+    }
+
+    /// The `WordId`s of every word, in the same order as `words`.
+    pub fn ids(&self) -> impl Iterator<Item = WordId> {
+        (0..self.words.len()).map(WordId::from_index)
+    }
+
+    /// The opening guess to use with this dictionary, if one has been determined up front.
+    pub fn first_guess(&self) -> Option<&'static str> {
+        self.first_guess
+    }
+
+    /// A hash of the words, counts, and answers of this dictionary, for telling apart results
+    /// that were computed for different dictionaries.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut bytes = Vec::new();
+        for &(word, count) in self.words {
+            bytes.extend_from_slice(word.as_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.push(0);
+        for answer in self.answers {
+            bytes.extend_from_slice(answer.as_bytes());
+            bytes.push(0);
+        }
+        crate::fnv1a(&[&bytes])
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads all of `path` into a string that lives for the rest of the program.
+///
+/// The words of a `Dictionary` are `&'static str`s, so the file they come from can never be freed.
+fn read_leaked(path: &Path) -> io::Result<&'static str> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(String::leak(contents))
+}
@@ -0,0 +1,155 @@
+use crate::{
+    max_mask_enum, Correctness, Dictionary, Mask, PackedCorrectness, WordId, MAX_ALPHABET_SIZE,
+};
+
+/// How a game scores a guess against the answer.
+///
+/// The `Solver` only ever looks at feedback through this trait, so the same solver can play any
+/// game where a guess is scored against a hidden word, like Wordle or Jotto.
+///
+/// Each possible feedback is an _outcome_ numbered from 0 up to (but not including)
+/// `outcomes(word_length)`, which lets the solver tally them up in an array. Outcomes are cached
+/// per dictionary and per type, so implementations have to be stateless.
+pub trait Feedback: Copy + Default + std::fmt::Debug + Send + Sync + 'static {
+    /// The number of distinct outcomes for words of `word_length` letters.
+    fn outcomes(word_length: usize) -> usize;
+
+    /// The outcome of guessing `guess` when the answer is `answer`, with both words given as
+    /// letter indices into the same `Alphabet`.
+    fn compute(answer: &[u8], guess: &[u8]) -> u32;
+
+    /// True if `outcome` means that the guess was the answer.
+    fn is_solved(outcome: u32, word_length: usize) -> bool;
+
+    /// A known good opening guess for `dictionary`, if there is one.
+    fn opener(_dictionary: &Dictionary) -> Option<&'static str> {
+        None
+    }
+
+    /// Calls `f` with every outcome that could be shown for `outcome` if exactly `lies` parts of
+    /// it were lies, like in Fibble.
+    ///
+    /// Panics if this feedback has no notion of lying.
+    fn for_each_lie(outcome: u32, word_length: usize, lies: usize, f: impl FnMut(u32)) {
+        let _ = (outcome, word_length, lies, f);
+        panic!("{:?} can't lie", Self::default());
+    }
+}
+
+/// A guess along with the outcome it got under some `Feedback`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scored {
+    pub word: WordId,
+    pub outcome: u32,
+}
+
+/// The feedback of Wordle: the `Correctness` of every letter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordleFeedback;
+
+impl WordleFeedback {
+    /// The outcome that stands for `mask`.
+    pub fn outcome(mask: Mask) -> u32 {
+        u32::from(PackedCorrectness::from(mask))
+    }
+
+    /// The mask for words of `word_length` letters that `outcome` stands for; the inverse of
+    /// `outcome`.
+    pub fn mask(mut outcome: u32, word_length: usize) -> Mask {
+        let mut mask = Mask::all(Correctness::Wrong, word_length);
+        // The last letter is the least significant digit.
+        for c in mask.iter_mut().rev() {
+            *c = match outcome % 3 {
+                0 => Correctness::Correct,
+                1 => Correctness::Misplaced,
+                _ => Correctness::Wrong,
+            };
+            outcome /= 3;
+        }
+        mask
+    }
+}
+
+impl Feedback for WordleFeedback {
+    fn outcomes(word_length: usize) -> usize {
+        max_mask_enum(word_length)
+    }
+
+    fn compute(answer: &[u8], guess: &[u8]) -> u32 {
+        Self::outcome(Correctness::compute_symbols(answer, guess))
+    }
+
+    fn is_solved(outcome: u32, word_length: usize) -> bool {
+        outcome == Self::outcome(Mask::all(Correctness::Correct, word_length))
+    }
+
+    fn opener(dictionary: &Dictionary) -> Option<&'static str> {
+        dictionary.first_guess()
+    }
+
+    /// A lie is a tile shown in one of the two colors it doesn't actually have.
+    fn for_each_lie(outcome: u32, word_length: usize, lies: usize, mut f: impl FnMut(u32)) {
+        fn lie_from(
+            outcome: u32,
+            from: usize,
+            word_length: usize,
+            lies: usize,
+            f: &mut impl FnMut(u32),
+        ) {
+            if lies == 0 {
+                f(outcome);
+                return;
+            }
+            // Only lie about tiles after the last one we lied about, so that every combination of
+            // tiles is visited once.
+            for tile in from..word_length {
+                // Outcomes are packed with the first tile as the most significant base-3 digit.
+                let place = 3u32.pow((word_length - 1 - tile) as u32);
+                let truth = outcome / place % 3;
+                for shown in (0..3).filter(|&shown| shown != truth) {
+                    let outcome = outcome - truth * place + shown * place;
+                    lie_from(outcome, tile + 1, word_length, lies - 1, f);
+                }
+            }
+        }
+        lie_from(outcome, 0, word_length, lies, &mut f)
+    }
+}
+
+/// The feedback of Jotto: how many letters the guess has in common with the answer, regardless of
+/// where they are.
+///
+/// Since anagrams have all of their letters in common, guessing the answer itself is an outcome of
+/// its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JottoFeedback;
+
+impl Feedback for JottoFeedback {
+    fn outcomes(word_length: usize) -> usize {
+        // 0..=word_length letters in common, plus one for the answer itself.
+        word_length + 2
+    }
+
+    fn compute(answer: &[u8], guess: &[u8]) -> u32 {
+        if answer == guess {
+            return answer.len() as u32 + 1;
+        }
+        let mut unmatched = [0u8; MAX_ALPHABET_SIZE];
+        for &a in answer {
+            unmatched[usize::from(a)] += 1;
+        }
+        let mut common = 0;
+        for &g in guess {
+            let n = &mut unmatched[usize::from(g)];
+            if *n > 0 {
+                *n -= 1;
+                common += 1;
+            }
+        }
+        common
+    }
+
+    fn is_solved(outcome: u32, word_length: usize) -> bool {
+        outcome == word_length as u32 + 1
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{Correctness, Guess};
+
+/// The rules `Wordle::play` holds a guesser to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GameConfig {
+    /// How many guesses the guesser gets before the game is lost.
+    pub max_guesses: usize,
+
+    /// If true, every guess must be a word from the dictionary.
+    pub require_dictionary: bool,
+
+    /// If true, every guess must follow the rules of hard mode; see [`check_hard_mode`].
+    pub hard_mode: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            // Wordle only allows six guesses.
+            max_guesses: 6,
+            require_dictionary: true,
+            hard_mode: false,
+        }
+    }
+}
+
+/// The ways in which a guess can break the rules of a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleViolation {
+    /// The guess doesn't have the same number of letters as the answer.
+    WrongLength { guess: String, expected: usize },
+
+    /// The guess isn't in the dictionary.
+    NotInDictionary { guess: String },
+
+    /// The guess doesn't use the hints revealed so far, which hard mode disallows.
+    HardMode {
+        guess: String,
+        violation: HardModeViolation,
+    },
+}
+
+impl std::fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongLength { guess, expected } => {
+                write!(f, "guess '{}' is not {} letters long", guess, expected)
+            }
+            Self::NotInDictionary { guess } => {
+                write!(f, "guess '{}' is not in the dictionary", guess)
+            }
+            Self::HardMode { guess, violation } => {
+                write!(f, "guess '{}' breaks hard mode: {}", guess, violation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleViolation {}
+
+/// The ways in which a guess can fail to use the hints revealed by earlier guesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardModeViolation {
+    /// A letter that was revealed to be `Correct` isn't reused in the same position.
+    ///
+    /// `position` is 0-based.
+    MissingCorrect { letter: char, position: usize },
+
+    /// A letter that was revealed to be in the answer `count` times is used fewer times than that.
+    MissingLetter { letter: char, count: usize },
+}
+
+impl std::fmt::Display for HardModeViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::MissingCorrect { letter, position } => {
+                let n = position + 1;
+                let suffix = match (n % 10, n % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                write!(
+                    f,
+                    "{}{} letter must be {}",
+                    n,
+                    suffix,
+                    letter.to_uppercase()
+                )
+            }
+            Self::MissingLetter { letter, count: 1 } => {
+                write!(f, "guess must contain {}", letter.to_uppercase())
+            }
+            Self::MissingLetter { letter, count } => write!(
+                f,
+                "guess must contain {} at least {} times",
+                letter.to_uppercase(),
+                count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HardModeViolation {}
+
+/// Checks that `guess` follows the rules of hard mode as played on the New York Times website,
+/// given the guesses made so far.
+///
+/// Every letter revealed as `Correct` must be reused in the same position, and every letter
+/// revealed as `Correct` or `Misplaced` must be used again, as many times as it was revealed.
+/// Unlike a guess that could still be the answer, a guess may reuse `Wrong` letters, and may put a
+/// `Misplaced` letter back where it was.
+///
+/// Expects `guess` to be as long as the earlier guesses.
+pub fn check_hard_mode(history: &[Guess], guess: &str) -> Result<(), HardModeViolation> {
+    for earlier in history {
+        let letters = || earlier.word.chars().zip(earlier.mask.iter());
+        for (position, (letter, &c)) in letters().enumerate() {
+            if c == Correctness::Correct && guess.chars().nth(position) != Some(letter) {
+                return Err(HardModeViolation::MissingCorrect { letter, position });
+            }
+        }
+        for (letter, &c) in letters() {
+            if c == Correctness::Wrong {
+                continue;
+            }
+            let count = letters()
+                .filter(|&(l, &c)| l == letter && c != Correctness::Wrong)
+                .count();
+            if guess.chars().filter(|&l| l == letter).count() < count {
+                return Err(HardModeViolation::MissingLetter { letter, count });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How a game ended, provided the guesser played by the rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// The answer was guessed with this many guesses.
+    Won(usize),
+
+    /// The guesser ran out of guesses.
+    Lost,
+}
+
+/// Everything that happened in a game played with `Wordle::play`.
+#[derive(Debug)]
+pub struct GameOutcome {
+    pub answer: &'static str,
+
+    /// Every guess that was scored, along with its feedback, including the winning one.
+    ///
+    /// A guess that broke the rules ends the game, and is not included.
+    pub transcript: Vec<Guess<'static>>,
+
+    pub result: Result<GameResult, RuleViolation>,
+}
+
+impl GameOutcome {
+    /// The number of guesses it took to win, or `None` if the game wasn't won.
+    pub fn score(&self) -> Option<usize> {
+        match self.result {
+            Ok(GameResult::Won(n)) => Some(n),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{Correctness, Dictionary, Mask, ParseMaskError, Word};
+use std::fmt;
+
+/// The ways in which a share grid can be malformed, or not fit a dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    /// A row of the grid couldn't be parsed. `line` starts at 1.
+    Row { line: usize, error: ParseMaskError },
+
+    /// The grid has no rows.
+    Empty,
+
+    /// A row doesn't have as many tiles as the words of the dictionary have letters. `row` starts
+    /// at 1.
+    WrongLength { row: usize, expected: usize },
+
+    /// The answer isn't in the dictionary.
+    InvalidAnswer { answer: String },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Row { line, error } => write!(f, "line {}: {}", line, error),
+            Self::Empty => write!(f, "the grid has no rows"),
+            Self::WrongLength { row, expected } => {
+                write!(f, "row {} doesn't have {} tiles", row, expected)
+            }
+            Self::InvalidAnswer { answer } => {
+                write!(f, "answer '{}' is not in the dictionary", answer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/// Which guesses could have produced one row of a share grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowAnalysis {
+    pub mask: Mask,
+
+    /// Every word that could have been guessed, along with how likely it is to be the one that was
+    /// guessed given how common it is, from most to least likely.
+    pub words: Vec<(&'static str, f64)>,
+
+    /// How likely it is for a guess to produce this row, with guesses weighed by how common they
+    /// are.
+    pub likelihood: f64,
+}
+
+/// Which answers and guesses could have produced a share grid; see [`analyze_grid`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridAnalysis {
+    /// The answers that every row of the grid could have come from.
+    pub answers: Vec<&'static str>,
+    pub rows: Vec<RowAnalysis>,
+}
+
+/// Parses a share grid, like the one the NYT lets you post after playing, into its rows.
+///
+/// Each row is a `Mask` in any `Notation`, on a line of its own. Blank lines are skipped, as is
+/// any text before the first row, such as the `Wordle 1,234 4/6` header.
+pub fn parse_grid(grid: &str) -> Result<Vec<Mask>, GridError> {
+    let mut rows = Vec::new();
+    for (i, line) in grid.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(mask) => rows.push(mask),
+            Err(_) if rows.is_empty() => continue,
+            Err(error) => return Err(GridError::Row { line: i + 1, error }),
+        }
+    }
+    if rows.is_empty() {
+        return Err(GridError::Empty);
+    }
+    Ok(rows)
+}
+
+/// Works out which words of `dictionary` could have been guessed to produce each row of `grid`.
+///
+/// If `answer` is not given, every answer of `dictionary` that could have produced all of the rows
+/// is considered.
+pub fn analyze_grid(
+    dictionary: &Dictionary,
+    grid: &[Mask],
+    answer: Option<&str>,
+) -> Result<GridAnalysis, GridError> {
+    if grid.is_empty() {
+        return Err(GridError::Empty);
+    }
+    let length = dictionary.word_length();
+    if let Some(row) = grid.iter().position(|mask| mask.len() != length) {
+        return Err(GridError::WrongLength {
+            row: row + 1,
+            expected: length,
+        });
+    }
+
+    let answers: Vec<(&'static str, Word)> = match answer {
+        Some(answer) => {
+            let id = dictionary
+                .id(answer)
+                .ok_or_else(|| GridError::InvalidAnswer {
+                    answer: answer.to_string(),
+                })?;
+            vec![(dictionary.word(id), *dictionary.encoded(id.index()))]
+        }
+        None => dictionary
+            .answers()
+            .iter()
+            .filter_map(|&answer| Some((answer, dictionary.alphabet().encode(answer)?)))
+            .filter(|(_, answer)| {
+                // Every row needs at least one guess that could have produced it.
+                let mut produced = vec![false; grid.len()];
+                for idx in 0..dictionary.len() {
+                    let mask = Correctness::compute_symbols(answer, dictionary.encoded(idx));
+                    for (produced, row) in produced.iter_mut().zip(grid) {
+                        *produced |= mask == *row;
+                    }
+                }
+                produced.into_iter().all(|p| p)
+            })
+            .collect(),
+    };
+
+    let total: usize = dictionary.words().iter().map(|&(_, count)| count).sum();
+    let mut rows: Vec<_> = grid
+        .iter()
+        .map(|&mask| RowAnalysis {
+            mask,
+            words: Vec::new(),
+            likelihood: 0.0,
+        })
+        .collect();
+    let mut produced = vec![false; grid.len()];
+    for (idx, &(word, count)) in dictionary.words().iter().enumerate() {
+        let guess = dictionary.encoded(idx);
+        produced.fill(false);
+        for (_, answer) in &answers {
+            let mask = Correctness::compute_symbols(answer, guess);
+            for (produced, row) in produced.iter_mut().zip(grid) {
+                *produced |= mask == *row;
+            }
+        }
+        for (row, _) in rows.iter_mut().zip(&produced).filter(|(_, &p)| p) {
+            row.words.push((word, count as f64));
+        }
+    }
+    for row in &mut rows {
+        let sum: f64 = row.words.iter().map(|&(_, count)| count).sum();
+        if sum == 0.0 {
+            continue;
+        }
+        for (_, p) in &mut row.words {
+            *p /= sum;
+        }
+        // The words are sorted by count already, and so stay sorted by likelihood.
+        row.likelihood = sum / total as f64;
+    }
+
+    Ok(GridAnalysis {
+        answers: answers.into_iter().map(|(answer, _)| answer).collect(),
+        rows,
+    })
+}
@@ -1,61 +1,419 @@
 #![allow(clippy::type_complexity)]
-#![allow(clippy::blocks_in_if_conditions)]
+#![allow(clippy::blocks_in_conditions)]
 
-use std::{borrow::Cow, collections::HashSet, num::NonZeroU16};
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU32};
+mod alphabet;
+mod dictionary;
+mod feedback;
+mod game;
+mod grid;
+mod notation;
 mod solver;
-pub use solver::{Rank, Solver};
+mod transcript;
+mod tree;
+pub use alphabet::{Alphabet, Word, MAX_ALPHABET_SIZE};
+pub use dictionary::{Dictionary, WordId};
+pub use feedback::{Feedback, JottoFeedback, Scored, WordleFeedback};
+pub use game::{
+    check_hard_mode, GameConfig, GameOutcome, GameResult, HardModeViolation, RuleViolation,
+};
+pub use grid::{analyze_grid, parse_grid, GridAnalysis, GridError, RowAnalysis};
+pub use notation::{Notation, ParseMaskError};
+pub use solver::{
+    ExactSolver, Lookahead, MultiSolver, Options, Prior, Rank, Shape, Solution, Solver, StepsModel,
+    XordleSolver,
+};
+pub use transcript::{Divergence, RecordedOptions, Transcript, Turn};
+pub use tree::{Strategy, TreeGuesser};
 
-// change to 5 or 6
-pub const WORD_LENGTH: usize = 5;
-pub const GAMES: &str = include_str!("../answers-5.txt");
-
-// static FIRST_GUESS: &str = "presev";
-static FIRST_GUESS: &str = "tares";
-
-include!(concat!(env!("OUT_DIR"), "/dictionary.rs"));
+/// The shortest word length we support.
+pub const MIN_WORD_LENGTH: usize = 4;
+/// The longest word length we support.
+pub const MAX_WORD_LENGTH: usize = 11;
+/// The word length used when none is given.
+pub const DEFAULT_WORD_LENGTH: usize = 5;
 
 pub struct Wordle {
-    dictionary: HashSet<&'static str>,
-}
-
-impl Default for Wordle {
-    fn default() -> Self {
-        Self::new()
-    }
+    dictionary: &'static Dictionary,
+    config: GameConfig,
 }
 
 impl Wordle {
-    pub fn new() -> Self {
+    pub fn with_dictionary(dictionary: &'static Dictionary) -> Self {
         Self {
-            dictionary: HashSet::from_iter(DICTIONARY.iter().copied().map(|(word, _)| word)),
+            dictionary,
+            config: GameConfig::default(),
+        }
+    }
+
+    /// Sets the rules that `play` enforces.
+    pub fn with_config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn dictionary(&self) -> &'static Dictionary {
+        self.dictionary
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Plays a game of Wordle with `answer` as the answer, holding `guesser` to the rules in
+    /// `config`.
+    ///
+    /// Panics if `answer` isn't as long as the words of the dictionary.
+    pub fn play<G: Guesser>(&self, answer: &'static str, guesser: G) -> GameOutcome {
+        assert_eq!(
+            answer.chars().count(),
+            self.dictionary.word_length(),
+            "answer '{}' doesn't fit the dictionary",
+            answer
+        );
+        let mut transcript = Vec::new();
+        let result = self.play_into(answer, guesser, &mut transcript);
+        GameOutcome {
+            answer,
+            transcript,
+            result,
+        }
+    }
+
+    fn play_into<G: Guesser>(
+        &self,
+        answer: &'static str,
+        mut guesser: G,
+        transcript: &mut Vec<Guess<'static>>,
+    ) -> Result<GameResult, RuleViolation> {
+        for i in 1..=self.config.max_guesses {
+            let guess = guesser.guess(transcript);
+            self.check(&guess, transcript)?;
+            let correctness = Correctness::compute(answer, &guess);
+            transcript.push(Guess {
+                word: Cow::Owned(guess),
+                mask: correctness,
+            });
+            if correctness.is_solved() {
+                guesser.finish(i);
+                return Ok(GameResult::Won(i));
+            }
+        }
+        Ok(GameResult::Lost)
+    }
+
+    /// Like `play`, but for a guesser that picks words by their `WordId`.
+    ///
+    /// Neither the guesses nor the transcript allocate any strings, and the feedback is computed
+    /// from the dictionary's pre-encoded words, which makes this the fastest way to run a
+    /// guesser over many games. The guesser must use the same dictionary as this game.
+    pub fn play_ids<G: IdGuesser>(&self, answer: &'static str, guesser: G) -> GameOutcome {
+        assert!(
+            std::ptr::eq(guesser.dictionary(), self.dictionary),
+            "the guesser uses a different dictionary than the game"
+        );
+        let mut transcript = Vec::with_capacity(self.config.max_guesses);
+        let result = self.play_ids_into(answer, guesser, &mut transcript);
+        GameOutcome {
+            answer,
+            transcript,
+            result,
+        }
+    }
+
+    fn play_ids_into<G: IdGuesser>(
+        &self,
+        answer: &'static str,
+        mut guesser: G,
+        transcript: &mut Vec<Guess<'static>>,
+    ) -> Result<GameResult, RuleViolation> {
+        let encoded_answer = self
+            .dictionary
+            .alphabet()
+            .encode(answer)
+            .filter(|a| a.len() == self.dictionary.word_length())
+            .unwrap_or_else(|| panic!("answer '{}' doesn't fit the dictionary", answer));
+        let mut history = Vec::with_capacity(self.config.max_guesses);
+        for i in 1..=self.config.max_guesses {
+            let id = guesser.guess_id(&history);
+            let guess = self.dictionary.word(id);
+            // Words from the dictionary always have the right length, so only the hints need
+            // checking.
+            self.check_hints(guess, transcript)?;
+            let correctness =
+                Correctness::compute_symbols(&encoded_answer, self.dictionary.encoded(id.index()));
+            transcript.push(Guess {
+                word: Cow::Borrowed(guess),
+                mask: correctness,
+            });
+            history.push(IdGuess {
+                word: id,
+                mask: correctness,
+            });
+            if correctness.is_solved() {
+                guesser.finish(i);
+                return Ok(GameResult::Won(i));
+            }
         }
+        Ok(GameResult::Lost)
     }
 
-    pub fn play<G: Guesser>(&self, answer: &'static str, mut guesser: G) -> Option<usize> {
+    /// Plays a game where guesses are scored by `F` instead of by the rules of Wordle, like Jotto.
+    ///
+    /// Of the rules in `config`, only `max_guesses` applies, since the others are specific to
+    /// Wordle.
+    pub fn play_scored<F: Feedback>(
+        &self,
+        answer: &'static str,
+        mut solver: Solver<F>,
+    ) -> Result<GameResult, RuleViolation> {
+        assert!(
+            std::ptr::eq(solver.options().dictionary, self.dictionary),
+            "the solver uses a different dictionary than the game"
+        );
+        let length = self.dictionary.word_length();
+        let encoded_answer = self
+            .dictionary
+            .alphabet()
+            .encode(answer)
+            .filter(|a| a.len() == length)
+            .unwrap_or_else(|| panic!("answer '{}' doesn't fit the dictionary", answer));
         let mut history = Vec::new();
-        // Wordle only allows six guesses.
-        // We allow more to avoid chopping off the score distribution for stats purposes.
-        for i in 1..=32 {
+        for i in 1..=self.config.max_guesses {
+            let id = solver.guess_scored(&history);
+            let outcome = F::compute(&encoded_answer, self.dictionary.encoded(id.index()));
+            if F::is_solved(outcome, length) {
+                solver.finish(i);
+                return Ok(GameResult::Won(i));
+            }
+            history.push(Scored { word: id, outcome });
+        }
+        Ok(GameResult::Lost)
+    }
+
+    /// Plays a game of Fibble, where exactly `lies` tiles of the feedback for every guess are shown
+    /// in a color they don't actually have.
+    ///
+    /// Which tiles lie, and what they show instead, is picked pseudo-randomly from the answer and
+    /// the guess, so the same game always gets the same feedback. The game is won once the guess
+    /// is the answer, whatever the feedback says. Of the rules in `config`, only `max_guesses`
+    /// applies, since hard mode can't be enforced with feedback that lies.
+    pub fn play_fibble<G: IdGuesser>(
+        &self,
+        answer: &'static str,
+        lies: usize,
+        mut guesser: G,
+    ) -> Result<GameResult, RuleViolation> {
+        assert!(
+            std::ptr::eq(guesser.dictionary(), self.dictionary),
+            "the guesser uses a different dictionary than the game"
+        );
+        let length = self.dictionary.word_length();
+        assert!(lies <= length, "words are too short to have that many lies");
+        let encoded_answer = self
+            .dictionary
+            .alphabet()
+            .encode(answer)
+            .filter(|a| a.len() == length)
+            .unwrap_or_else(|| panic!("answer '{}' doesn't fit the dictionary", answer));
+        let mut history = Vec::new();
+        for i in 1..=self.config.max_guesses {
+            let id = guesser.guess_id(&history);
+            let guess = self.dictionary.encoded(id.index());
+            if *guess == encoded_answer {
+                guesser.finish(i);
+                return Ok(GameResult::Won(i));
+            }
+            let correctness = Correctness::compute_symbols(&encoded_answer, guess);
+            history.push(IdGuess {
+                word: id,
+                mask: tell_lies(correctness, lies, fnv1a(&[&encoded_answer, guess])),
+            });
+        }
+        Ok(GameResult::Lost)
+    }
+
+    /// Plays a game of Xordle, where there are two answers that share no letters, and the feedback
+    /// for each guess is that of both answers merged with `Mask::merge`.
+    ///
+    /// Guessing one of the answers gets an all `Correct` mask, but so can other words, since each
+    /// letter may match either answer. The game is won once both answers have been guessed. Of the
+    /// rules in `config`, only `max_guesses` applies, since hard mode can't be enforced with
+    /// merged feedback.
+    pub fn play_xordle<G: IdGuesser>(
+        &self,
+        answers: [&'static str; 2],
+        mut guesser: G,
+    ) -> Result<GameResult, RuleViolation> {
+        assert!(
+            std::ptr::eq(guesser.dictionary(), self.dictionary),
+            "the guesser uses a different dictionary than the game"
+        );
+        assert!(
+            !answers[0].chars().any(|c| answers[1].contains(c)),
+            "the answers '{}' and '{}' share letters",
+            answers[0],
+            answers[1]
+        );
+        let length = self.dictionary.word_length();
+        let encoded = answers.map(|answer| {
+            self.dictionary
+                .alphabet()
+                .encode(answer)
+                .filter(|a| a.len() == length)
+                .unwrap_or_else(|| panic!("answer '{}' doesn't fit the dictionary", answer))
+        });
+        let mut found = [false; 2];
+        let mut history = Vec::new();
+        for i in 1..=self.config.max_guesses {
+            let id = guesser.guess_id(&history);
+            let guess = self.dictionary.encoded(id.index());
+            let [a, b] = encoded
+                .each_ref()
+                .map(|answer| Correctness::compute_symbols(answer, guess));
+            found[0] |= a.is_solved();
+            found[1] |= b.is_solved();
+            if found == [true, true] {
+                guesser.finish(i);
+                return Ok(GameResult::Won(i));
+            }
+            history.push(IdGuess {
+                word: id,
+                mask: a.merge(b),
+            });
+        }
+        Ok(GameResult::Lost)
+    }
+
+    /// Checks that `guess` follows the rules in `config`.
+    fn check(&self, guess: &str, history: &[Guess]) -> Result<(), RuleViolation> {
+        self.check_word(guess)?;
+        self.check_hints(guess, history)
+    }
+
+    /// Checks that `guess` is a word that may be guessed at all, whatever was guessed before it.
+    fn check_word(&self, guess: &str) -> Result<(), RuleViolation> {
+        let expected = self.dictionary.word_length();
+        if guess.chars().count() != expected {
+            return Err(RuleViolation::WrongLength {
+                guess: guess.to_string(),
+                expected,
+            });
+        }
+        if self.config.require_dictionary && !self.dictionary.contains(guess) {
+            return Err(RuleViolation::NotInDictionary {
+                guess: guess.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that `guess` uses the hints revealed so far, if `config` asks for hard mode.
+    fn check_hints(&self, guess: &str, history: &[Guess]) -> Result<(), RuleViolation> {
+        if self.config.hard_mode {
+            check_hard_mode(history, guess).map_err(|violation| RuleViolation::HardMode {
+                guess: guess.to_string(),
+                violation,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Plays against an adversarial host, like Absurdle, that never commits to an answer.
+    ///
+    /// After each guess, the host groups the answers it hasn't ruled out yet by the feedback they
+    /// would produce, and replies with the feedback of the largest group. Ties go to the feedback
+    /// with the fewest `Correct`, then the fewest `Misplaced` letters. The game is won once the
+    /// guess is the only answer left. The guesser is held to the rules in `config`.
+    pub fn play_adversarial<G: Guesser>(
+        &self,
+        mut guesser: G,
+    ) -> Result<GameResult, RuleViolation> {
+        let mut remaining = self.dictionary.answers().to_vec();
+        let mut history = Vec::new();
+        for i in 1..=self.config.max_guesses {
             let guess = guesser.guess(&history);
-            if guess == answer {
+            self.check(&guess, &history)?;
+            let mut buckets: HashMap<Mask, Vec<&'static str>> = HashMap::new();
+            for &answer in &remaining {
+                buckets
+                    .entry(Correctness::compute(answer, &guess))
+                    .or_default()
+                    .push(answer);
+            }
+            let (mask, bucket) = buckets
+                .into_iter()
+                .max_by_key(|(mask, bucket)| {
+                    let count = |c| mask.iter().filter(|&&m| m == c).count();
+                    (
+                        bucket.len(),
+                        std::cmp::Reverse(count(Correctness::Correct)),
+                        std::cmp::Reverse(count(Correctness::Misplaced)),
+                        *mask,
+                    )
+                })
+                .expect("there is always at least one answer left");
+            if mask.is_solved() {
                 guesser.finish(i);
-                return Some(i);
+                return Ok(GameResult::Won(i));
             }
-            assert!(
-                self.dictionary.contains(&*guess),
-                "guess '{}' is not in the dictionary",
-                guess
-            );
-            let correctness = Correctness::compute(answer, &guess);
+            remaining = bucket;
             history.push(Guess {
                 word: Cow::Owned(guess),
-                mask: correctness,
+                mask,
             });
         }
-        None
+        Ok(GameResult::Lost)
+    }
+
+    /// Plays a game where every guess is scored against all of `answers` at once, like Dordle (2
+    /// answers), Quordle (4), or Octordle (8).
+    ///
+    /// Each answer has its own board, which is solved once it has been guessed. Of the rules in
+    /// `config`, hard mode doesn't apply, since a guess can't follow the hints of every board.
+    ///
+    /// Panics if one of `answers` isn't as long as the words of the dictionary.
+    pub fn play_multi<G: MultiGuesser>(
+        &self,
+        answers: &[&'static str],
+        mut guesser: G,
+    ) -> Result<GameResult, RuleViolation> {
+        for answer in answers {
+            assert_eq!(
+                answer.chars().count(),
+                self.dictionary.word_length(),
+                "answer '{}' doesn't fit the dictionary",
+                answer
+            );
+        }
+        let mut boards: Vec<Vec<Guess>> = answers.iter().map(|_| Vec::new()).collect();
+        let mut unsolved = answers.len();
+        for i in 1..=self.config.max_guesses {
+            let guess = guesser.guess(&boards);
+            self.check_word(&guess)?;
+            for (&answer, history) in answers.iter().zip(&mut boards) {
+                if history.last().is_some_and(|g| g.mask.is_solved()) {
+                    continue;
+                }
+                let correctness = Correctness::compute(answer, &guess);
+                if correctness.is_solved() {
+                    unsolved -= 1;
+                }
+                history.push(Guess {
+                    word: Cow::Owned(guess.clone()),
+                    mask: correctness,
+                });
+            }
+            if unsolved == 0 {
+                guesser.finish(i);
+                return Ok(GameResult::Won(i));
+            }
+        }
+        Ok(GameResult::Lost)
     }
 }
 
+/// Ordered from best to worst, so that the better of two is their `min`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Correctness {
     /// Green
@@ -67,10 +425,10 @@ pub enum Correctness {
 }
 
 impl Correctness {
-    fn is_misplaced(letter: u16, answer: &str, used: &mut [bool; WORD_LENGTH]) -> bool {
-        let mut enumerated = answer.bytes().enumerate();
-        enumerated.any(|(i, a)| {
-            if u16::from(a) == letter && !used[i] {
+    fn is_misplaced(letter: u8, answer: &[u8], used: &mut [bool]) -> bool {
+        let mut enumerated = answer.iter().enumerate();
+        enumerated.any(|(i, &a)| {
+            if a == letter && !used[i] {
                 used[i] = true;
                 return true;
             }
@@ -78,30 +436,33 @@ impl Correctness {
         })
     }
 
-    pub fn compute(answer: &str, guess: &str) -> [Self; WORD_LENGTH] {
-        assert_eq!(answer.len(), WORD_LENGTH);
-        assert_eq!(guess.len(), WORD_LENGTH);
-        let mut c = [Correctness::Wrong; WORD_LENGTH];
-        let answer_bytes = answer.as_bytes();
-        let guess_bytes = guess.as_bytes();
-        // Array indexed by lowercase ascii letters
-        let mut misplaced = [0u16; (b'z' - b'a' + 1) as usize];
+    pub fn compute(answer: &str, guess: &str) -> Mask {
+        let (answer, guess) = Word::encode_pair(answer, guess).expect("word is too long");
+        Self::compute_symbols(&answer, &guess)
+    }
+
+    /// Like `compute`, but for words given as letter indices into an `Alphabet`.
+    pub fn compute_symbols(answer: &[u8], guess: &[u8]) -> Mask {
+        assert_eq!(answer.len(), guess.len());
+        let mut c = Mask::all(Correctness::Wrong, guess.len());
+        // Array indexed by letter
+        let mut misplaced = [0u8; MAX_ALPHABET_SIZE];
 
         // Find all correct letters
-        for ((&answer, &guess), c) in answer_bytes.iter().zip(guess_bytes).zip(c.iter_mut()) {
+        for ((&answer, &guess), c) in answer.iter().zip(guess).zip(c.iter_mut()) {
             if answer == guess {
                 *c = Correctness::Correct
             } else {
                 // If the letter does not match, count it as misplaced
-                misplaced[(answer - b'a') as usize] += 1;
+                misplaced[usize::from(answer)] += 1;
             }
         }
         // Check all of the non matching letters if they are misplaced
-        for (&guess, c) in guess_bytes.iter().zip(c.iter_mut()) {
+        for (&guess, c) in guess.iter().zip(c.iter_mut()) {
             // If the letter was guessed wrong and the same letter was counted as misplaced
-            if *c == Correctness::Wrong && misplaced[(guess - b'a') as usize] > 0 {
+            if *c == Correctness::Wrong && misplaced[usize::from(guess)] > 0 {
                 *c = Correctness::Misplaced;
-                misplaced[(guess - b'a') as usize] -= 1;
+                misplaced[usize::from(guess)] -= 1;
             }
         }
 
@@ -109,83 +470,202 @@ impl Correctness {
     }
 }
 
-pub const MAX_MASK_ENUM: usize = 3_usize.pow(WORD_LENGTH as u32);
+/// The `Correctness` of each letter of a guess.
+///
+/// This is stored inline with room for the longest supported word so that masks can be passed
+/// around without allocating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Mask {
+    len: u8,
+    // Entries past `len` are always `Wrong` so that the derived impls only see the real letters.
+    tiles: [Correctness; MAX_WORD_LENGTH],
+}
+
+impl Mask {
+    /// A mask of `len` letters that all have the same `Correctness`.
+    pub fn all(c: Correctness, len: usize) -> Self {
+        assert!(len <= MAX_WORD_LENGTH);
+        let mut tiles = [Correctness::Wrong; MAX_WORD_LENGTH];
+        tiles[..len].fill(c);
+        Self {
+            len: len as u8,
+            tiles,
+        }
+    }
+
+    /// True if every letter is `Correct`, i.e., the guess was the answer.
+    pub fn is_solved(&self) -> bool {
+        self.iter().all(|&c| c == Correctness::Correct)
+    }
+
+    /// The better `Correctness` of each letter of two masks for the same guess, like the feedback
+    /// of Xordle merges the feedback for both of its answers.
+    pub fn merge(mut self, other: Self) -> Self {
+        assert_eq!(self.len(), other.len());
+        for (c, &o) in self.iter_mut().zip(other.iter()) {
+            *c = (*c).min(o);
+        }
+        self
+    }
+}
+
+impl From<&[Correctness]> for Mask {
+    fn from(c: &[Correctness]) -> Self {
+        let mut mask = Self::all(Correctness::Wrong, c.len());
+        mask.copy_from_slice(c);
+        mask
+    }
+}
+
+impl<const N: usize> From<[Correctness; N]> for Mask {
+    fn from(c: [Correctness; N]) -> Self {
+        Self::from(&c[..])
+    }
+}
+
+impl std::ops::Deref for Mask {
+    type Target = [Correctness];
+
+    fn deref(&self) -> &Self::Target {
+        &self.tiles[..usize::from(self.len)]
+    }
+}
+
+impl std::ops::DerefMut for Mask {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tiles[..usize::from(self.len)]
+    }
+}
+
+/// The number of distinct masks for words of length `word_length`.
+pub fn max_mask_enum(word_length: usize) -> usize {
+    3_usize.pow(word_length as u32)
+}
+
+/// Shows exactly `lies` tiles of `mask` in one of the colors they don't have, picking the tiles and
+/// colors with a generator seeded by `seed`.
+fn tell_lies(mut mask: Mask, lies: usize, seed: u64) -> Mask {
+    let mut rng = Rng::new(seed);
+    let mut tiles: [usize; MAX_WORD_LENGTH] = std::array::from_fn(|i| i);
+    for i in 0..lies {
+        // A partial Fisher-Yates shuffle, so that no tile lies twice.
+        let j = i + rng.below(mask.len() - i);
+        tiles.swap(i, j);
+        let truth = mask[tiles[i]];
+        let mut colors = [
+            Correctness::Correct,
+            Correctness::Misplaced,
+            Correctness::Wrong,
+        ]
+        .into_iter()
+        .filter(|&c| c != truth);
+        mask[tiles[i]] = colors.nth(rng.below(2)).unwrap();
+    }
+    mask
+}
+
+/// xorshift64*, which is plenty random for picking tiles or answers, and always picks the same ones
+/// for the same seed.
+///
+/// It is only public so that the benchmarks can use it too.
+#[doc(hidden)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be 0.
+        Self(seed | 1)
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+/// The 64-bit FNV-1a hash of `parts`, one after the other.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
 
-/// A wrapper type for `[Correctness; WORD_LENGTH]` packed into a single byte with a niche.
+/// A wrapper type for `Mask` packed into a single integer with a niche.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(transparent)]
-// The NonZeroU16 here lets the compiler know that we're not using the value `0`, and that `0` can
+// The NonZeroU32 here lets the compiler know that we're not using the value `0`, and that `0` can
 // therefore be used to represent `None` for `Option<PackedCorrectness>`.
-struct PackedCorrectness(NonZeroU16);
-
-impl From<[Correctness; WORD_LENGTH]> for PackedCorrectness {
-    fn from(c: [Correctness; WORD_LENGTH]) -> Self {
-        println!();
-        let packed = c.iter().fold(0, |acc: u16, c| {
-            let old_acc = acc;
-            println!("acc {old_acc:#010b} * 3");
-            let multiplied_acc = acc.checked_mul(3_u16);
-            let multiplied_acc = match multiplied_acc {
-                Some(multiplied_acc) => multiplied_acc,
-                None => {
-                    panic!();
-                }
-            };
-
-            let correctness_value = match c {
-                Correctness::Correct => 0,
-                Correctness::Misplaced => 1,
-                Correctness::Wrong => 2,
-            };
+//
+// It has to be a `u32`, since 3^11 doesn't fit in a `u16`.
+struct PackedCorrectness(NonZeroU32);
 
-            let sum = multiplied_acc + correctness_value;
-            sum
+impl From<Mask> for PackedCorrectness {
+    fn from(c: Mask) -> Self {
+        let packed = c.iter().fold(0, |acc, c| {
+            acc * 3
+                + match c {
+                    Correctness::Correct => 0,
+                    Correctness::Misplaced => 1,
+                    Correctness::Wrong => 2,
+                }
         });
-        Self(NonZeroU16::new(packed + 1).unwrap())
+        Self(NonZeroU32::new(packed + 1).unwrap())
     }
 }
 
-impl From<PackedCorrectness> for u16 {
+impl From<PackedCorrectness> for u32 {
     fn from(this: PackedCorrectness) -> Self {
         this.0.get() - 1
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Guess<'a> {
     pub word: Cow<'a, str>,
-    pub mask: [Correctness; WORD_LENGTH],
+    pub mask: Mask,
 }
 
 impl Guess<'_> {
     pub fn matches(&self, word: &str) -> bool {
+        let (word, guess) = Word::encode_pair(word, &self.word).expect("word is too long");
+        Self::matches_symbols(&guess, &self.mask, &word)
+    }
+
+    /// Like `matches`, but for words given as letter indices into an `Alphabet`.
+    pub fn matches_symbols(guess: &[u8], mask: &[Correctness], word: &[u8]) -> bool {
         // Check if the guess would be possible to observe when `word` is the correct answer.
         // This is equivalent to
-        //     Correctness::compute(word, &self.word) == self.mask
+        //     Correctness::compute_symbols(word, guess) == mask
         // without _necessarily_ computing the full mask for the tested word
-        assert_eq!(word.len(), WORD_LENGTH);
-        assert_eq!(self.word.len(), WORD_LENGTH);
-        let mut used = [false; WORD_LENGTH];
+        assert_eq!(word.len(), guess.len());
+        assert_eq!(mask.len(), guess.len());
+        let mut used = [false; MAX_WORD_LENGTH];
+        let used = &mut used[..word.len()];
 
         // Check Correct letters
-        for (i, (a, g)) in word.bytes().zip(self.word.bytes()).enumerate() {
+        for (i, (a, g)) in word.iter().zip(guess).enumerate() {
             if a == g {
-                if self.mask[i] != Correctness::Correct {
+                if mask[i] != Correctness::Correct {
                     return false;
                 }
                 used[i] = true;
-            } else if self.mask[i] == Correctness::Correct {
+            } else if mask[i] == Correctness::Correct {
                 return false;
             }
         }
 
         // Check Misplaced letters
-        for (g, e) in self.word.bytes().zip(self.mask.iter()) {
+        for (&g, e) in guess.iter().zip(mask) {
             if *e == Correctness::Correct {
                 continue;
             }
-            if Correctness::is_misplaced(g.into(), word, &mut used)
-                != (*e == Correctness::Misplaced)
-            {
+            if Correctness::is_misplaced(g, word, used) != (*e == Correctness::Misplaced) {
                 return false;
             }
         }
@@ -200,6 +680,62 @@ pub trait Guesser {
     fn finish(&self, _guesses: usize) {}
 }
 
+/// Like `Guess`, but identifies the guessed word by its `WordId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdGuess {
+    pub word: WordId,
+    pub mask: Mask,
+}
+
+/// A guesser that picks words by their `WordId` in a `Dictionary`; see [`Wordle::play_ids`].
+///
+/// Unlike a `Guesser`, it doesn't need to allocate a `String` for every guess, nor look up the
+/// words of earlier guesses to know which ones they were. Every `IdGuesser` is also a `Guesser`.
+pub trait IdGuesser {
+    /// The dictionary that the `WordId`s refer to.
+    fn dictionary(&self) -> &'static Dictionary;
+    fn guess_id(&mut self, history: &[IdGuess]) -> WordId;
+    fn finish(&self, _guesses: usize) {}
+}
+
+impl<G: IdGuesser> Guesser for G {
+    fn guess(&mut self, history: &[Guess]) -> String {
+        let dictionary = self.dictionary();
+        let history: Vec<_> = history
+            .iter()
+            .map(|guess| IdGuess {
+                word: dictionary
+                    .id(&guess.word)
+                    .unwrap_or_else(|| panic!("guess '{}' is not in the dictionary", guess.word)),
+                mask: guess.mask,
+            })
+            .collect();
+        dictionary.word(self.guess_id(&history)).to_string()
+    }
+
+    fn finish(&self, guesses: usize) {
+        IdGuesser::finish(self, guesses)
+    }
+}
+
+/// A guesser for games with several boards; see [`Wordle::play_multi`].
+pub trait MultiGuesser {
+    /// `boards` holds the guesses made so far for each board. A board is solved once its last
+    /// guess was all `Correct`, after which no more guesses are added to it.
+    fn guess(&mut self, boards: &[Vec<Guess>]) -> String;
+    fn finish(&self, _guesses: usize) {}
+}
+
+#[cfg(test)]
+macro_rules! mask {
+    (@C) => {$crate::Correctness::Correct};
+    (@M) => {$crate::Correctness::Misplaced};
+    (@W) => {$crate::Correctness::Wrong};
+    ($($c:tt)+) => {
+        $crate::Mask::from([$(mask!(@$c)),+])
+    };
+}
+
 #[cfg(test)]
 macro_rules! guesser {
     (|$history:ident| $impl:block) => {{
@@ -215,86 +751,1270 @@ macro_rules! guesser {
 
 #[cfg(test)]
 mod tests {
+    use crate::{Dictionary, Options};
+
+    /// Options for a dictionary of six words, where the `abc?` words are hard to tell apart.
+    fn tiny_options() -> Options {
+        let d = Dictionary::parse("abcd 5\nabce 4\nabcf 3\nabgh 2\nijkl 1\nijkm 1");
+        Options::new(d.unwrap().leak())
+    }
+
     mod game {
-        use crate::{Guess, Wordle, WORD_LENGTH};
+        use crate::Correctness::{Correct as C, Misplaced as M, Wrong as W};
+        use crate::{
+            check_hard_mode, Dictionary, GameConfig, GameResult, Guess, HardModeViolation, Mask,
+            RuleViolation, Wordle,
+        };
+        use std::borrow::Cow;
+
+        /// A game where `right` and `wrong` are the only words.
+        fn wordle() -> Wordle {
+            Wordle::with_dictionary(Dictionary::parse("right 2\nwrong 1").unwrap().leak())
+        }
 
         #[test]
         fn genius() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|_history| { "right".to_string() });
-            assert_eq!(w.play("right", guesser), Some(1));
+            assert_eq!(w.play("right", guesser).score(), Some(1));
         }
 
         #[test]
         fn magnificent() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|history| {
                 if history.len() == 1 {
                     return "right".to_string();
                 }
                 "wrong".to_string()
             });
-            assert_eq!(w.play("right", guesser), Some(2));
+            assert_eq!(w.play("right", guesser).score(), Some(2));
         }
 
         #[test]
         fn impressive() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|history| {
                 if history.len() == 2 {
                     return "right".to_string();
                 }
                 "wrong".to_string()
             });
-            assert_eq!(w.play("right", guesser), Some(3));
+            assert_eq!(w.play("right", guesser).score(), Some(3));
         }
 
         #[test]
         fn splendid() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|history| {
                 if history.len() == 3 {
                     return "right".to_string();
                 }
                 "wrong".to_string()
             });
-            assert_eq!(w.play("right", guesser), Some(4));
+            assert_eq!(w.play("right", guesser).score(), Some(4));
         }
 
         #[test]
         fn great() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|history| {
                 if history.len() == 4 {
                     return "right".to_string();
                 }
                 "wrong".to_string()
             });
-            assert_eq!(w.play("right", guesser), Some(WORD_LENGTH));
+            assert_eq!(w.play("right", guesser).score(), Some(5));
         }
 
         #[test]
         fn phew() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|history| {
-                if history.len() == WORD_LENGTH {
+                if history.len() == 5 {
                     return "right".to_string();
                 }
                 "wrong".to_string()
             });
-            assert_eq!(w.play("right", guesser), Some(6));
+            assert_eq!(w.play("right", guesser).score(), Some(6));
         }
 
         #[test]
         fn oops() {
-            let w = Wordle::new();
+            let w = wordle();
             let guesser = guesser!(|_history| { "wrong".to_string() });
-            assert_eq!(w.play("right", guesser), None);
+            assert_eq!(w.play("right", guesser).score(), None);
+        }
+
+        #[test]
+        fn transcript() {
+            let w = wordle();
+            let guesser = guesser!(|_history| { "wrong".to_string() });
+            let outcome = w.play("right", guesser);
+            assert_eq!(outcome.result, Ok(GameResult::Lost));
+            assert_eq!(outcome.transcript.len(), 6);
+            assert!(outcome.transcript.iter().all(|g| g.word == "wrong"));
+        }
+
+        #[test]
+        fn not_in_dictionary() {
+            let w = wordle();
+            let guesser = guesser!(|_history| { "zzzzz".to_string() });
+            let outcome = w.play("right", guesser);
+            assert_eq!(
+                outcome.result,
+                Err(RuleViolation::NotInDictionary {
+                    guess: "zzzzz".to_string()
+                })
+            );
+            assert!(outcome.transcript.is_empty());
+
+            let w = wordle().with_config(GameConfig {
+                require_dictionary: false,
+                ..Default::default()
+            });
+            let guesser = guesser!(|_history| { "zzzzz".to_string() });
+            assert_eq!(w.play("right", guesser).result, Ok(GameResult::Lost));
+        }
+
+        #[test]
+        fn hard_mode() {
+            let d = Dictionary::parse("right 3\nwrong 2\nfight 1")
+                .unwrap()
+                .leak();
+            let w = Wordle::with_dictionary(d).with_config(GameConfig {
+                hard_mode: true,
+                ..Default::default()
+            });
+            let guesser = guesser!(|history| {
+                if history.is_empty() {
+                    "wrong".to_string()
+                } else {
+                    "fight".to_string()
+                }
+            });
+            let outcome = w.play("right", guesser);
+            assert_eq!(
+                outcome.result,
+                Err(RuleViolation::HardMode {
+                    guess: "fight".to_string(),
+                    violation: HardModeViolation::MissingLetter {
+                        letter: 'r',
+                        count: 1
+                    }
+                })
+            );
+            assert_eq!(outcome.transcript.len(), 1);
+
+            // Repeating a guess always uses all of its hints.
+            let guesser = guesser!(|_history| { "wrong".to_string() });
+            let outcome = w.play("right", guesser);
+            assert_eq!(outcome.result, Ok(GameResult::Lost));
+        }
+
+        #[test]
+        fn hard_mode_rules() {
+            let history = [Guess {
+                word: Cow::Borrowed("sheet"),
+                mask: Mask::from([C, W, M, M, W]),
+            }];
+            assert_eq!(check_hard_mode(&history, "seven"), Ok(()));
+            // Misplaced letters may go back where they were.
+            assert_eq!(check_hard_mode(&history, "steep"), Ok(()));
+            let violation = check_hard_mode(&history, "tepee").unwrap_err();
+            assert_eq!(
+                violation,
+                HardModeViolation::MissingCorrect {
+                    letter: 's',
+                    position: 0
+                }
+            );
+            assert_eq!(violation.to_string(), "1st letter must be S");
+            let violation = check_hard_mode(&history, "sepal").unwrap_err();
+            assert_eq!(
+                violation,
+                HardModeViolation::MissingLetter {
+                    letter: 'e',
+                    count: 2
+                }
+            );
+            assert_eq!(
+                violation.to_string(),
+                "guess must contain E at least 2 times"
+            );
+        }
+
+        #[test]
+        fn absurdle() {
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let w = Wordle::with_dictionary(d);
+            let guesser = guesser!(|history| {
+                match history.len() {
+                    // rules out wxyz
+                    0 => "wxyz",
+                    // leaves abce and abcf
+                    1 => "abcd",
+                    // prefers abcf over a win
+                    2 => "abce",
+                    _ => "abcf",
+                }
+                .to_string()
+            });
+            assert_eq!(w.play_adversarial(guesser), Ok(GameResult::Won(4)));
+
+            let guesser = guesser!(|_history| { "zzzz".to_string() });
+            assert_eq!(
+                w.play_adversarial(guesser),
+                Err(RuleViolation::NotInDictionary {
+                    guess: "zzzz".to_string()
+                })
+            );
+
+            let w = w.with_config(GameConfig {
+                max_guesses: 3,
+                ..Default::default()
+            });
+            let guesser =
+                guesser!(|history| { ["wxyz", "abcd", "abce", "abcf"][history.len()].to_string() });
+            assert_eq!(w.play_adversarial(guesser), Ok(GameResult::Lost));
+        }
+
+        #[test]
+        #[should_panic(expected = "answer 'wrongs' doesn't fit the dictionary")]
+        fn answer_too_long() {
+            let guesser = guesser!(|_history| { "right".to_string() });
+            wordle().play("wrongs", guesser);
+        }
+
+        #[test]
+        fn play_ids() {
+            let options = super::tiny_options();
+            let d = options.dictionary;
+            let w = Wordle::with_dictionary(d);
+            for &answer in d.answers() {
+                let by_word = w.play(answer, options.build());
+                let by_id = w.play_ids(answer, options.build());
+                assert_eq!(by_word.result, by_id.result);
+                let words = |o: &crate::GameOutcome| {
+                    Vec::from_iter(o.transcript.iter().map(|g| (g.word.to_string(), g.mask)))
+                };
+                assert_eq!(words(&by_word), words(&by_id));
+            }
+        }
+
+        #[test]
+        fn dordle() {
+            struct G;
+            impl crate::MultiGuesser for G {
+                fn guess(&mut self, boards: &[Vec<Guess>]) -> String {
+                    assert_eq!(boards.len(), 2);
+                    match boards[0].len() {
+                        0 => "wrong".to_string(),
+                        // the second board was solved by the first guess
+                        1 => {
+                            assert_eq!(boards[1].len(), 1);
+                            "right".to_string()
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            let w = wordle();
+            assert_eq!(w.play_multi(&["right", "wrong"], G), Ok(GameResult::Won(2)));
+        }
+
+        #[test]
+        fn multi_solver() {
+            let mut options = super::tiny_options();
+            let d = options.dictionary;
+            let w = Wordle::with_dictionary(d);
+            for hard_mode in [true, false] {
+                options.hard_mode = hard_mode;
+                for answers in d.answers().windows(2) {
+                    let result = w.play_multi(answers, options.build_multi(2));
+                    assert!(matches!(result, Ok(GameResult::Won(_))), "{:?}", answers);
+                }
+            }
+        }
+    }
+
+    mod dictionary {
+        use crate::Dictionary;
+
+        #[test]
+        fn parse() {
+            let d = Dictionary::parse("abcd 1\nwxyz 3\nefgh 2\n").unwrap();
+            assert_eq!(d.word_length(), 4);
+            assert_eq!(d.words(), [("wxyz", 3), ("efgh", 2), ("abcd", 1)]);
+            assert_eq!(d.answers().len(), 3);
+
+            let d = d.with_answers("efgh\nabcd").unwrap();
+            assert_eq!(d.answers(), ["efgh", "abcd"]);
+            assert!(d.with_answers("ijkl").is_err());
+
+            let d = Dictionary::parse("čaša 1\nžaba 2").unwrap();
+            assert_eq!(d.word_length(), 4);
+            assert_eq!(d.alphabet().len(), 5);
+        }
+
+        #[test]
+        fn parse_invalid() {
+            assert!(Dictionary::parse("").is_err());
+            assert!(Dictionary::parse("abcd").is_err());
+            assert!(Dictionary::parse("abcd x").is_err());
+            assert!(Dictionary::parse("abcd 1\nabcde 1").is_err());
+            assert!(Dictionary::parse("abc 1").is_err());
+            assert!(Dictionary::parse("abcd 1\nefgh 2\nabcd 3").is_err());
+        }
+    }
+
+    mod transcript {
+        use crate::Correctness::{Correct as C, Wrong as W};
+        use crate::{Dictionary, Guess, Mask, Options, Transcript, Wordle};
+        use std::borrow::Cow;
+
+        #[test]
+        fn roundtrip() {
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let options = Options::new(d);
+            let outcome = Wordle::with_dictionary(d).play_ids("abcf", options.build());
+            let t = Transcript::from_outcome(&outcome, d, Some(options));
+            assert_eq!(t.answer.as_deref(), Some("abcf"));
+            assert_eq!(t.turns[0].candidates, 4);
+
+            let mut buf = Vec::new();
+            t.write(&mut buf).unwrap();
+            t.write(&mut buf).unwrap();
+            assert_eq!(buf.iter().filter(|&&b| b == b'\n').count(), 2);
+            let read = Transcript::read_all(&buf[..]).unwrap();
+            assert_eq!(read, vec![t.clone(), t.clone()]);
+            assert_eq!(t.replay(options), Ok(Vec::new()));
+        }
+
+        #[test]
+        fn replay_diverges() {
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let options = Options::new(d);
+            let history = [
+                Guess {
+                    word: Cow::Borrowed("wxyz"),
+                    mask: Mask::from([W, W, W, W]),
+                },
+                Guess {
+                    word: Cow::Borrowed("abcf"),
+                    mask: Mask::from([C, C, C, C]),
+                },
+            ];
+            let t = Transcript::new(None, &history, d, None);
+            assert_eq!(
+                Vec::from_iter(t.turns.iter().map(|turn| turn.candidates)),
+                [4, 3]
+            );
+            let divergences = t.replay(options).unwrap();
+            assert_eq!(divergences[0].turn, 1);
+            assert_eq!(divergences[0].played, "wxyz");
+        }
+
+        #[test]
+        fn unknown_letters() {
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let history = [
+                Guess {
+                    word: Cow::Borrowed("wxyq"),
+                    mask: Mask::from([W, W, W, W]),
+                },
+                Guess {
+                    word: Cow::Borrowed("abcf"),
+                    mask: Mask::from([C, C, C, C]),
+                },
+            ];
+            let t = Transcript::new(None, &history, d, None);
+            assert_eq!(
+                Vec::from_iter(t.turns.iter().map(|turn| turn.candidates)),
+                [4, 3]
+            );
+        }
+
+        #[test]
+        fn read_invalid() {
+            let err = Transcript::read_all(
+                &b"{\"turns\":[{\"guess\":\"abcd\",\"mask\":\"CXWW\",\"candidates\":1}]}"[..],
+            )
+            .unwrap_err();
+            assert!(err.to_string().starts_with("line 1:"), "{}", err);
+        }
+    }
+
+    mod alphabet {
+        use crate::{Alphabet, Correctness, Guess, Mask, Word};
+        use std::borrow::Cow;
+
+        use Correctness::{Correct as C, Misplaced as M, Wrong as W};
+
+        #[test]
+        fn encode() {
+            let a = Alphabet::from_words(["žaba", "čaša"]).unwrap();
+            assert_eq!(a.symbols(), ['a', 'b', 'č', 'š', 'ž']);
+            let w = a.encode("šaba").unwrap();
+            assert_eq!(&*w, [3, 0, 1, 0]);
+            assert_eq!(a.decode(&w), "šaba");
+            assert_eq!(a.encode("miza"), None);
+        }
+
+        #[test]
+        fn encode_pair() {
+            let (a, b) = Word::encode_pair("čaša", "šača").unwrap();
+            assert_eq!((&*a, &*b), (&[0, 1, 2, 1][..], &[2, 1, 0, 1][..]));
+            let long = "abcdefghijk";
+            assert!(Word::encode_pair(long, "lmnopqrstuv").is_some());
+            assert_eq!(Word::encode_pair(long, "lmnopqrstuvw"), None);
+            assert_eq!(Word::encode_pair("lmnopqrstuvw", long), None);
+        }
+
+        #[test]
+        fn unicode_compute() {
+            assert_eq!(
+                Correctness::compute("čaša", "šača"),
+                Mask::from([M, C, M, C])
+            );
+            assert_eq!(
+                Correctness::compute("niño", "ñoño"),
+                Mask::from([W, W, C, C])
+            );
+            assert_eq!(
+                Correctness::compute("größe", "ößgre"),
+                Mask::from([M, M, M, M, C])
+            );
+        }
+
+        #[test]
+        fn unicode_matches() {
+            let guess = Guess {
+                word: Cow::Borrowed("šača"),
+                mask: Mask::from([M, C, M, C]),
+            };
+            assert!(guess.matches("čaša"));
+            assert!(!guess.matches("žaba"));
+        }
+    }
+
+    mod notation {
+        use crate::{Mask, Notation, ParseMaskError};
+
+        #[test]
+        fn parse() {
+            let mask = mask![C M W W C];
+            for s in [
+                "CMWWC",
+                "cmwwc",
+                "C M W W C",
+                "GYBBG",
+                "🟩🟨⬛⬜🟩",
+                "🟧🟦⬛⬛🟧",
+                "21002",
+            ] {
+                assert_eq!(s.parse::<Mask>(), Ok(mask), "{}", s);
+            }
+            // Some platforms add a variation selector to the squares.
+            assert_eq!("🟩🟨⬛\u{FE0F}⬛\u{FE0F}🟩".parse::<Mask>(), Ok(mask));
+        }
+
+        #[test]
+        fn parse_invalid() {
+            assert_eq!("".parse::<Mask>(), Err(ParseMaskError::Empty));
+            assert_eq!(
+                "CMWGC".parse::<Mask>(),
+                Err(ParseMaskError::InvalidSymbol {
+                    symbol: 'G',
+                    notation: Some(Notation::Letters)
+                })
+            );
+            assert_eq!(
+                "XMWWC".parse::<Mask>(),
+                Err(ParseMaskError::InvalidSymbol {
+                    symbol: 'X',
+                    notation: None
+                })
+            );
+            assert_eq!(
+                "CCCCCCCCCCCC".parse::<Mask>(),
+                Err(ParseMaskError::TooLong { len: 12 })
+            );
+        }
+
+        #[test]
+        fn display() {
+            let mask = mask![C M W W C];
+            assert_eq!(mask.to_string(), "CMWWC");
+            assert_eq!(mask.display(Notation::Colors).to_string(), "GYBBG");
+            assert_eq!(mask.display(Notation::Emoji).to_string(), "🟩🟨⬛⬛🟩");
+            assert_eq!(mask.display(Notation::Digits).to_string(), "21002");
+            for notation in Notation::ALL {
+                let shown = mask.display(notation).to_string();
+                assert_eq!(Mask::parse_with(&shown, notation), Ok(mask));
+            }
+        }
+    }
+
+    mod feedback {
+        use crate::{
+            Dictionary, Feedback, GameResult, JottoFeedback, Options, Rank, Word, Wordle,
+            WordleFeedback,
+        };
+
+        fn jotto(answer: &str, guess: &str) -> u32 {
+            let (answer, guess) = Word::encode_pair(answer, guess).unwrap();
+            JottoFeedback::compute(&answer, &guess)
+        }
+
+        #[test]
+        fn jotto_compute() {
+            assert_eq!(jotto("abcde", "fghij"), 0);
+            assert_eq!(jotto("abcde", "edcba"), 5);
+            assert_eq!(jotto("abcde", "abcde"), 6);
+            // Repeated letters only count as often as they appear in both words.
+            assert_eq!(jotto("aabbb", "aaacc"), 2);
+            assert_eq!(jotto("abccc", "cxxxx"), 1);
+            assert!(JottoFeedback::is_solved(6, 5));
+            assert!(!JottoFeedback::is_solved(5, 5));
+        }
+
+        #[test]
+        fn wordle_solved() {
+            let (answer, guess) = Word::encode_pair("abcde", "abcde").unwrap();
+            assert!(WordleFeedback::is_solved(
+                WordleFeedback::compute(&answer, &guess),
+                5
+            ));
+            let (answer, guess) = Word::encode_pair("abcde", "edcba").unwrap();
+            assert!(!WordleFeedback::is_solved(
+                WordleFeedback::compute(&answer, &guess),
+                5
+            ));
+        }
+
+        #[test]
+        fn play_jotto() {
+            let d = Dictionary::parse("abcd 5\nbcda 4\nefgh 3\nabef 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let w = Wordle::with_dictionary(d);
+            let options = Options::<JottoFeedback>::new(d);
+            for &answer in d.answers() {
+                assert!(
+                    matches!(
+                        w.play_scored(answer, options.build()),
+                        Ok(GameResult::Won(_))
+                    ),
+                    "{}",
+                    answer
+                );
+            }
+        }
+
+        #[test]
+        fn wordle_lies() {
+            let truth = WordleFeedback::outcome(mask![C M W W]);
+            let mut shown = Vec::new();
+            WordleFeedback::for_each_lie(truth, 4, 1, |o| shown.push(o));
+            // Each of the 4 tiles can be shown in 2 other colors.
+            assert_eq!(shown.len(), 8);
+            assert!(shown.contains(&WordleFeedback::outcome(mask![W M W W])));
+            assert!(shown.contains(&WordleFeedback::outcome(mask![C M W C])));
+            assert!(!shown.contains(&truth));
+
+            let mut count = 0;
+            WordleFeedback::for_each_lie(truth, 4, 2, |_| count += 1);
+            assert_eq!(count, 6 * 4);
+            WordleFeedback::for_each_lie(truth, 4, 5, |_| unreachable!());
+        }
+
+        #[test]
+        fn tell_lies() {
+            let truth = mask![C M W W C];
+            for seed in 0..100 {
+                for lies in 0..=5 {
+                    let shown = crate::tell_lies(truth, lies, seed);
+                    let differ = truth.iter().zip(shown.iter()).filter(|(t, s)| t != s);
+                    assert_eq!(differ.count(), lies);
+                }
+            }
+        }
+
+        #[test]
+        fn merge() {
+            assert_eq!(mask![C W W M].merge(mask![W M W C]), mask![C M W C]);
+        }
+
+        #[test]
+        fn play_xordle() {
+            let d = Dictionary::parse("abcd 5\nefgh 4\nabch 3\nefgd 2\nijkl 1\nmnop 1")
+                .unwrap()
+                .leak();
+            let w = Wordle::with_dictionary(d);
+            let mut options = Options::new(d);
+            for (rank_by, hard_mode) in [
+                (Rank::ExpectedScore, true),
+                (Rank::ExpectedScore, false),
+                (Rank::Minimax, true),
+            ] {
+                options.rank_by = rank_by;
+                options.hard_mode = hard_mode;
+                for pair in [["abcd", "efgh"], ["efgd", "abch"], ["ijkl", "mnop"]] {
+                    assert!(
+                        matches!(
+                            w.play_xordle(pair, options.build_xordle()),
+                            Ok(GameResult::Won(_))
+                        ),
+                        "{:?}",
+                        pair
+                    );
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "the Xordle solver can't guarantee a win")]
+        fn xordle_guarantee() {
+            let d = Dictionary::parse("abcd 2\nefgh 1").unwrap().leak();
+            let mut options = Options::new(d);
+            options.guarantee = true;
+            options.build_xordle();
+        }
+
+        #[test]
+        fn play_fibble() {
+            let d = Dictionary::parse("abcd 5\nabce 4\nabdc 3\nbadc 2\nwxyz 1\nwxyc 1")
+                .unwrap()
+                .leak();
+            let w = Wordle::with_dictionary(d);
+            let mut options = Options::new(d);
+            options.lies = 1;
+            for &answer in d.answers() {
+                let result = w.play_fibble(answer, 1, options.build());
+                assert!(matches!(result, Ok(GameResult::Won(_))), "{}", answer);
+            }
+        }
+    }
+
+    mod guarantee {
+        use crate::{Dictionary, GameConfig, Options, Rank, Wordle};
+
+        #[test]
+        fn within_limit() {
+            // The `abc?` words can only be told apart in time by guessing `defg` first, but `abcd`
+            // is so likely that it's tempting to guess it right away.
+            let d = Dictionary::parse("abcd 1000\nabce 8\nabcf 7\nabcg 6\nabch 5\ndefg 1")
+                .unwrap()
+                .with_answers("abcd abce abcf abcg abch")
+                .unwrap()
+                .leak();
+            let config = GameConfig {
+                max_guesses: 3,
+                ..GameConfig::default()
+            };
+            let w = Wordle::with_dictionary(d).with_config(config);
+            let mut options = Options::new(d);
+            options.sigmoid = false;
+            options.hard_mode = false;
+            for rank_by in [Rank::First, Rank::ExpectedScore] {
+                options.rank_by = rank_by;
+                options.max_guesses = 3;
+                options.guarantee = false;
+                let lost = d
+                    .answers()
+                    .iter()
+                    .filter(|&&answer| w.play_ids(answer, options.build()).score().is_none())
+                    .count();
+                assert_ne!(lost, 0);
+
+                options.guarantee = true;
+                for &answer in d.answers() {
+                    let outcome = w.play_ids(answer, options.build());
+                    assert!(outcome.score().is_some(), "{}", answer);
+                }
+            }
+        }
+
+        #[test]
+        fn win_probability() {
+            let d = Dictionary::parse("abcd 1\nabce 5\nwxyz 3\nabfg 2")
+                .unwrap()
+                .leak();
+            let mut options = Options::new(d);
+            options.sigmoid = false;
+            options.rank_by = Rank::WinProbability;
+            // With a single guess, all that matters is how likely the guess is to be the answer.
+            options.max_guesses = 1;
+            let w = Wordle::with_dictionary(d);
+            let outcome = w.play_ids("abce", options.build());
+            assert_eq!(outcome.transcript[0].word, "abce");
+
+            options.max_guesses = 6;
+            for &answer in d.answers() {
+                assert!(w.play_ids(answer, options.build()).score().is_some());
+            }
+        }
+    }
+
+    mod parallel {
+        use crate::{Dictionary, Options, Wordle};
+
+        #[test]
+        fn same_as_one_thread() {
+            // Enough words that ranking guesses is spread over threads.
+            let words: String = (0..400usize)
+                .map(|i| {
+                    let n = i * 37 % 4096;
+                    let word: String = (0..4)
+                        .map(|d| (b'a' + (n >> (3 * d) & 7) as u8) as char)
+                        .collect();
+                    format!("{} {}\n", word, 1 + i * 7 % 50)
+                })
+                .collect();
+            let d = Dictionary::parse(words.leak()).unwrap().leak();
+            let w = Wordle::with_dictionary(d);
+            let mut options = Options::new(d);
+            options.hard_mode = false;
+            options.cutoff = false;
+            let play = || -> Vec<Vec<String>> {
+                d.answers()
+                    .iter()
+                    .take(40)
+                    .map(|&answer| {
+                        let outcome = w.play_ids(answer, options.build());
+                        outcome
+                            .transcript
+                            .into_iter()
+                            .map(|g| g.word.into_owned())
+                            .collect()
+                    })
+                    .collect()
+            };
+            let one = rayon::ThreadPoolBuilder::new()
+                .num_threads(1)
+                .build()
+                .unwrap();
+            let many = rayon::ThreadPoolBuilder::new()
+                .num_threads(4)
+                .build()
+                .unwrap();
+            assert_eq!(one.install(play), many.install(play));
+        }
+    }
+
+    mod matrix {
+        use crate::Wordle;
+
+        #[test]
+        fn saved_and_checked() {
+            let options = super::tiny_options();
+            let d = options.dictionary;
+            let dir = std::env::temp_dir().join(format!("roget-matrix-{}", std::process::id()));
+            options.load_pattern_matrix(&dir).unwrap();
+            let path = std::fs::read_dir(&dir)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .path();
+            let saved = std::fs::read(&path).unwrap();
+            // A header, and a byte for every pair of words.
+            assert!(saved.len() > d.len() * d.len());
+
+            // Loading again maps the file, and the solver plays the same with it as without any.
+            options.load_pattern_matrix(&dir).unwrap();
+            let w = Wordle::with_dictionary(d);
+            for &answer in d.answers() {
+                let cached = w.play_ids(answer, options.build());
+                let computed = w.play_ids(
+                    answer,
+                    crate::Options {
+                        cache: false,
+                        ..options
+                    }
+                    .build(),
+                );
+                assert_eq!(cached.score(), computed.score());
+            }
+
+            // The file is mapped, so it must be replaced rather than written to.
+            let replace = |bytes: &[u8]| {
+                std::fs::remove_file(&path).unwrap();
+                std::fs::write(&path, bytes).unwrap();
+            };
+
+            // Damaged outcomes are only noticed when asked to verify them, and then replaced.
+            let mut corrupt = saved.clone();
+            *corrupt.last_mut().unwrap() ^= 1;
+            replace(&corrupt);
+            options.load_pattern_matrix(&dir).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), corrupt);
+            options.verify_pattern_matrix(&dir).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), saved);
+
+            // A header that doesn't match is noticed on every load.
+            let mut corrupt = saved.clone();
+            corrupt["roget patterns 1\n".len()] ^= 1;
+            replace(&corrupt);
+            options.load_pattern_matrix(&dir).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), saved);
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    mod prior {
+        use crate::{Dictionary, Options, Prior, RecordedOptions, Wordle};
+
+        #[test]
+        fn fit() {
+            // The answers are the common words that aren't plurals.
+            let d = Dictionary::parse(
+                "abcd 900\nabce 800\nbcda 700\nbcds 600\nacds 500\ndcba 400\ncdab 300\nbdcs 200\nadbs 100\ncbad 50\ndabc 20\nbacs 10",
+            )
+            .unwrap()
+            .with_answers("abcd abce bcda dcba cdab")
+            .unwrap()
+            .leak();
+            let prior = Prior::fit(d);
+            let p = prior.probabilities(d);
+            let worst_answer = d
+                .ids()
+                .filter(|&id| d.answers().contains(&d.word(id)))
+                .map(|id| p[id.index()])
+                .fold(f64::INFINITY, f64::min);
+            let best_other = d
+                .ids()
+                .filter(|&id| !d.answers().contains(&d.word(id)))
+                .map(|id| p[id.index()])
+                .fold(0.0, f64::max);
+            assert!(worst_answer > best_other, "{:?}", p);
+            assert!(prior.log_loss(d) < Prior::sigmoid_log_loss(d));
+
+            let mut written = Vec::new();
+            prior.write(&mut written).unwrap();
+            let text = String::from_utf8(written).unwrap();
+            assert_eq!(Prior::parse(&text).unwrap(), prior);
+            assert!(Prior::parse(&text.replace("plural", "plurals")).is_err());
+
+            let mut options = Options::new(d);
+            options.prior = Some(prior.leak());
+            let w = Wordle::with_dictionary(d);
+            for &answer in d.answers() {
+                assert!(w.play_ids(answer, options.build()).score().is_some());
+            }
+
+            // Replaying a transcript rebuilds its options for every game, which mustn't leak the
+            // prior again each time.
+            let recorded = RecordedOptions::from(options);
+            let first = recorded.with_dictionary(d).prior.unwrap();
+            let second = recorded.with_dictionary(d).prior.unwrap();
+            assert!(std::ptr::eq(first, second));
+            assert!(std::ptr::eq(first, options.prior.unwrap()));
+        }
+    }
+
+    mod steps {
+        use crate::{Shape, StepsModel};
+
+        #[test]
+        fn fit() {
+            // Every game takes one more guess for every two bits of entropy.
+            let text = "0 1\n2 2\n4 3\n\n6 4\n8 5\n10 6\n10 6\n8 5\n6 4\n4 3\n2 2\n0 1\n";
+            let data = StepsModel::parse_data(text).unwrap();
+            assert_eq!(data.len(), 12);
+            assert!(StepsModel::parse_data("1.5 0\n").is_err());
+            assert!(StepsModel::parse_data("1.5\n").is_err());
+
+            let linear = StepsModel::fit(Shape::Linear, &data);
+            assert!((linear.slope - 0.5).abs() < 1e-9, "{}", linear);
+            assert!((linear.estimate(5.0) - 3.5).abs() < 1e-9, "{}", linear);
+            let log = StepsModel::fit(Shape::Log, &data);
+            assert!(StepsModel::cross_validate(Shape::Linear, &data, 3) < 1e-9);
+
+            assert_eq!(log.to_string().parse::<StepsModel>(), Ok(log));
+            assert_eq!(
+                "log:3.87:3.679".parse::<StepsModel>(),
+                Ok(StepsModel::default())
+            );
+            assert!("cube:1:2".parse::<StepsModel>().is_err());
+            assert!("log:1".parse::<StepsModel>().is_err());
+        }
+    }
+
+    mod lookahead {
+        use crate::Wordle;
+
+        /// The words guessed for every answer of the dictionary, played on a pool of `threads`.
+        fn transcripts(options: crate::Options, threads: usize) -> Vec<Vec<String>> {
+            let d = options.dictionary;
+            let w = Wordle::with_dictionary(d);
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                d.answers()
+                    .iter()
+                    .map(|&answer| {
+                        let outcome = w.play_ids(answer, options.build());
+                        assert!(outcome.score().is_some(), "{}", answer);
+                        outcome
+                            .transcript
+                            .into_iter()
+                            .map(|g| g.word.into_owned())
+                            .collect()
+                    })
+                    .collect()
+            })
+        }
+
+        /// The average number of guesses over the answers of the dictionary, weighted by how
+        /// common each is.
+        fn expected_score(options: crate::Options) -> f64 {
+            let d = options.dictionary;
+            let count = |answer: &str| d.words().iter().find(|w| w.0 == answer).unwrap().1 as f64;
+            let (mut guesses, mut total) = (0.0, 0.0);
+            for (&answer, words) in d.answers().iter().zip(transcripts(options, 1)) {
+                guesses += count(answer) * words.len() as f64;
+                total += count(answer);
+            }
+            guesses / total
+        }
+
+        #[test]
+        fn same_as_one_ply_when_narrow() {
+            let options = super::tiny_options();
+            let mut narrow = options;
+            narrow.lookahead.depth = 2;
+            narrow.lookahead.width = 1;
+            assert_eq!(transcripts(options, 1), transcripts(narrow, 1));
+
+            // With nothing to budget for, the search falls back to the one-ply guess.
+            let mut broke = narrow;
+            broke.lookahead.width = 10;
+            broke.lookahead.budget = 0;
+            assert_eq!(transcripts(options, 1), transcripts(broke, 1));
+        }
+
+        #[test]
+        fn never_worse_than_one_ply() {
+            let options = super::tiny_options();
+            let one_ply = expected_score(options);
+            for (depth, width) in [(2, 10), (3, 10), (3, 2)] {
+                let mut deeper = options;
+                deeper.lookahead.depth = depth;
+                deeper.lookahead.width = width;
+                let score = expected_score(deeper);
+                assert!(
+                    score <= one_ply + 1e-9,
+                    "depth {}, width {}: {} > {}",
+                    depth,
+                    width,
+                    score,
+                    one_ply
+                );
+            }
+        }
+
+        #[test]
+        fn same_on_any_number_of_threads() {
+            let mut options = super::tiny_options();
+            options.lookahead.depth = 3;
+            options.lookahead.width = 10;
+            assert_eq!(transcripts(options, 1), transcripts(options, 4));
+        }
+    }
+
+    mod opener {
+        use crate::{Dictionary, Options, Rank, Wordle};
+
+        #[test]
+        fn rank_and_cache() {
+            let d = Dictionary::parse("abcd 5\nabce 4\nabcf 3\nefgh 2\nijkl 1")
+                .unwrap()
+                .leak();
+            let mut options = Options::new(d);
+            options.rank_by = Rank::ExpectedInformation;
+
+            let one = options.rank_openers(1, 3, None).unwrap();
+            assert_eq!(one.len(), 3);
+            assert!(one.windows(2).all(|w| w[0].1 >= w[1].1));
+
+            let two = options.rank_openers(2, 5, None).unwrap();
+            assert_eq!(two.len(), 5);
+            assert!(two.windows(2).all(|w| w[0].1 >= w[1].1));
+
+            let dir = std::env::temp_dir().join(format!("roget-opener-{}", std::process::id()));
+            let computed = options.rank_openers(2, 5, Some(&dir)).unwrap();
+            let cached = options.rank_openers(2, 5, Some(&dir)).unwrap();
+            assert_eq!(computed, cached);
+            std::fs::remove_dir_all(&dir).unwrap();
+
+            // The probability of winning depends on the guess limit, so a ranking for one limit
+            // mustn't be served for another.
+            let mut win = options;
+            win.rank_by = Rank::WinProbability;
+            win.max_guesses = 6;
+            let six = win.rank_openers(2, 5, Some(&dir)).unwrap();
+            win.max_guesses = 2;
+            let two_guesses = win.rank_openers(2, 5, Some(&dir)).unwrap();
+            assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+            assert_eq!(two_guesses, win.rank_openers(2, 5, None).unwrap());
+            assert_ne!(six, two_guesses);
+            std::fs::remove_dir_all(&dir).unwrap();
+
+            let options = options.with_computed_opener(2, None).unwrap();
+            assert_eq!(options.opener, Some(two[0].0));
+            let outcome = Wordle::with_dictionary(d).play_ids("ijkl", options.build());
+            assert_eq!(outcome.transcript[0].word, two[0].0);
+        }
+    }
+
+    mod exact {
+        use crate::{Correctness, Dictionary, Options, Strategy, Wordle};
+        use std::time::Duration;
+
+        /// The total number of guesses `strategy` takes over every answer, checking that it
+        /// does find each of them.
+        fn play(d: &Dictionary, strategy: &Strategy) -> usize {
+            d.answers()
+                .iter()
+                .map(|&answer| {
+                    let mut node = strategy;
+                    let mut guesses = 1;
+                    loop {
+                        let guess = d.word(node.guess);
+                        if guess == answer {
+                            break guesses;
+                        }
+                        let mask = Correctness::compute(answer, guess);
+                        node = &node.next.iter().find(|(m, _)| *m == mask).unwrap().1;
+                        guesses += 1;
+                    }
+                })
+                .sum()
+        }
+
+        #[test]
+        fn probe() {
+            // Guessing the answers one by one takes 1 + 2 + 3 + 4 guesses, but guessing `defg`
+            // first tells them all apart.
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nabcg 1\ndefg 1")
+                .unwrap()
+                .with_answers("abcd abce abcf abcg")
+                .unwrap()
+                .leak();
+            let mut options = Options::new(d);
+            options.hard_mode = false;
+            let solution = options.build_exact().solve().unwrap().unwrap();
+            assert_eq!(solution.total, 8);
+            assert!(solution.is_optimal());
+            assert_eq!(d.word(solution.strategy.guess), "defg");
+            assert_eq!(play(d, &solution.strategy), 8);
+
+            let mut exact = options.build_exact().with_max_guesses(1);
+            assert_eq!(exact.solve().unwrap(), None);
+        }
+
+        #[test]
+        fn beats_solver() {
+            let d = Dictionary::parse(
+                "abcd 9\nabce 8\nabcf 7\nabgh 6\nijkl 5\nijkm 4\nnopa 3\nbdfh 2\nkmoq 1",
+            )
+            .unwrap()
+            .leak();
+            let w = Wordle::with_dictionary(d);
+            let mut totals = Vec::new();
+            for hard_mode in [false, true] {
+                let mut options = Options::new(d);
+                options.hard_mode = hard_mode;
+                let solution = options.build_exact().solve().unwrap().unwrap();
+                assert!(solution.is_optimal());
+                assert_eq!(play(d, &solution.strategy), solution.total);
+                let solver: usize = d
+                    .answers()
+                    .iter()
+                    .map(|&answer| w.play_ids(answer, options.build()).score().unwrap())
+                    .sum();
+                assert!(solution.total <= solver, "{} > {}", solution.total, solver);
+                totals.push(solution.total);
+            }
+            // Hard mode only takes options away.
+            assert!(totals[0] <= totals[1]);
+        }
+
+        #[test]
+        fn checkpoint() {
+            let mut options = super::tiny_options();
+            let dir = std::env::temp_dir().join(format!("roget-exact-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("checkpoint");
+
+            let mut first = options
+                .build_exact()
+                .with_checkpoint(&path, Duration::ZERO)
+                .unwrap();
+            let solution = first.solve().unwrap();
+            let mut resumed = options
+                .build_exact()
+                .with_checkpoint(&path, Duration::ZERO)
+                .unwrap();
+            assert_eq!(resumed.positions(), first.positions());
+            assert_eq!(resumed.solve().unwrap(), solution);
+
+            // A checkpoint for other settings isn't picked up.
+            options.hard_mode = !options.hard_mode;
+            assert!(options
+                .build_exact()
+                .with_checkpoint(&path, Duration::ZERO)
+                .is_err());
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    mod tree {
+        use crate::{Strategy, TreeGuesser, Wordle};
+
+        #[test]
+        fn record_and_play() {
+            let options = super::tiny_options();
+            let d = options.dictionary;
+            let w = Wordle::with_dictionary(d);
+            let strategy = w.record_strategy(|| options.build());
+            assert_eq!(strategy.answers(), d.answers().len());
+            let mut total = 0;
+            for &answer in d.answers() {
+                let solver = w.play_ids(answer, options.build());
+                let tree = w.play_ids(answer, TreeGuesser::new(d, &strategy));
+                let words = |outcome: &crate::GameOutcome| {
+                    Vec::from_iter(outcome.transcript.iter().map(|g| g.word.clone()))
+                };
+                assert_eq!(words(&tree), words(&solver));
+                total += tree.score().unwrap();
+            }
+            assert_eq!(strategy.total(), total);
+        }
+
+        #[test]
+        fn text() {
+            let options = super::tiny_options();
+            let d = options.dictionary;
+            let strategy = Wordle::with_dictionary(d).record_strategy(|| options.build());
+            let mut text = Vec::new();
+            strategy.write(d, &mut text).unwrap();
+            let text = String::from_utf8(text).unwrap();
+            assert_eq!(text.lines().count(), d.answers().len());
+            assert_eq!(Strategy::parse(d, &text).unwrap(), strategy);
+        }
+
+        #[test]
+        fn parse() {
+            let d = super::tiny_options().dictionary;
+            let s = Strategy::parse(d, "ABCD GGGG1\n\nabcd gggb1 abce GGGG2\n").unwrap();
+            assert_eq!(s.answers(), 2);
+            assert_eq!(s.total(), 3);
+            // Digits are colors too, but the guess number comes after one per letter.
+            let s = Strategy::parse(d, "abcd 22201 abcf 22222").unwrap();
+            assert_eq!(s, Strategy::parse(d, "abcd GGGB1 abcf GGGG2").unwrap());
+            let s = Strategy::parse(d, "abcd 🟩🟩🟩⬛️1 abce 🟩🟩🟩🟩2").unwrap();
+            assert_eq!(d.word(s.next[0].1.guess), "abce");
+
+            for (tree, error) in [
+                ("", "the tree is empty"),
+                (
+                    "abcd",
+                    "line 1: expected every guess to be followed by its colors",
+                ),
+                ("abcz GGGG1", "line 1: 'abcz' is not in the dictionary"),
+                (
+                    "abcd GGGG2",
+                    "line 1: expected 'GGGG2' to end in the guess number, 1",
+                ),
+                (
+                    "abcd GGGB1 abcf GGGG",
+                    "line 1: expected 'GGGG' to end in the guess number, 2",
+                ),
+                (
+                    "abcd GGGGG1",
+                    "line 1: expected 'GGGGG1' to end in the guess number, 1",
+                ),
+                (
+                    "abcd GGBB1 abcf GGGG2",
+                    "line 1: guess 1 would get GGGB if the answer were 'abcf'",
+                ),
+                (
+                    "abcd GGGB1 abce GGGG2\nabcd GGGB1 abcf GGGB2 abce GGGG3",
+                    "line 2: guess 2 is 'abcf', but an earlier line guesses 'abce' there",
+                ),
+            ] {
+                let e = Strategy::parse(d, tree).unwrap_err();
+                assert_eq!(e.to_string(), error, "{}", tree);
+            }
+        }
+    }
+
+    mod grid {
+        use crate::{analyze_grid, parse_grid, Dictionary, GridError};
+
+        #[test]
+        fn parse() {
+            let rows = parse_grid("Wordle 1,234 2/6\n\n🟩🟩🟩⬛\n🟩🟩🟩🟩\n").unwrap();
+            assert_eq!(rows, [mask![C C C W], mask![C C C C]]);
+            assert_eq!(parse_grid("Wordle 1,234 X/6\n"), Err(GridError::Empty));
+            assert!(matches!(
+                parse_grid("🟩🟩🟩⬛\n🟩🟩x🟩"),
+                Err(GridError::Row { line: 2, .. })
+            ));
+        }
+
+        #[test]
+        fn analyze() {
+            let d = Dictionary::parse("abcd 4\nabce 3\nabcf 2\nwxyz 1")
+                .unwrap()
+                .leak();
+            let rows = [mask![C C C W], mask![C C C C]];
+
+            let analysis = analyze_grid(d, &rows, None).unwrap();
+            assert_eq!(analysis.answers, ["abcd", "abce", "abcf"]);
+            let words = Vec::from_iter(analysis.rows[0].words.iter().map(|&(w, _)| w));
+            assert_eq!(words, ["abcd", "abce", "abcf"]);
+            assert!((analysis.rows[0].likelihood - 0.9).abs() < 1e-9);
+
+            let analysis = analyze_grid(d, &rows, Some("abcf")).unwrap();
+            assert_eq!(analysis.answers, ["abcf"]);
+            assert_eq!(
+                analysis.rows[0].words,
+                [("abcd", 4.0 / 7.0), ("abce", 3.0 / 7.0)]
+            );
+            assert_eq!(analysis.rows[1].words, [("abcf", 1.0)]);
+
+            assert_eq!(
+                analyze_grid(d, &rows, Some("abcg")),
+                Err(GridError::InvalidAnswer {
+                    answer: "abcg".to_string()
+                })
+            );
+            assert_eq!(
+                analyze_grid(d, &[mask![C C C C C]], None),
+                Err(GridError::WrongLength {
+                    row: 1,
+                    expected: 4
+                })
+            );
         }
     }
 
     mod compute {
-        /* use crate::Correctness;
+        use crate::Correctness;
 
         #[test]
         fn all_green() {
@@ -339,6 +2059,6 @@ mod tests {
         #[test]
         fn ricoello_from_chat() {
             assert_eq!(Correctness::compute("abcde", "aacde"), mask![C W C C C]);
-        } */
+        }
     }
 }
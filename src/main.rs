@@ -1,8 +1,17 @@
 #![allow(deprecated)]
 use std::borrow::Cow;
+use std::cell::RefCell;
 
-use clap::{Parser, ValueEnum};
-use roget::{Guesser, Solver, GAMES, WORD_LENGTH};
+use clap::{Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use roget::{
+    Dictionary, GameConfig, GameOutcome, GameResult, Guesser, IdGuesser, MultiGuesser,
+    RuleViolation, Solver, Transcript,
+};
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[global_allocator]
 static GLOBAL_ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
@@ -11,16 +20,64 @@ static GLOBAL_ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    /// The number of letters in each word.
+    ///
+    /// Selects the matching built-in dictionary and answer list.
+    #[clap(short, long, default_value_t = roget::DEFAULT_WORD_LENGTH, conflicts_with = "dictionary")]
+    length: usize,
+
+    /// Load the allowed words from this file instead of using a built-in dictionary.
+    ///
+    /// Each line holds a word followed by a space and how common that word is. The word length is
+    /// taken from the words in the file. Unless --answers is also given, every word is a possible
+    /// answer.
+    #[clap(long)]
+    dictionary: Option<std::path::PathBuf>,
+
+    /// Load the answers to play from this file instead of using the built-in answer list.
+    ///
+    /// The answers are whitespace-separated, and must all be in the dictionary.
+    #[clap(long)]
+    answers: Option<std::path::PathBuf>,
+
     /// By default, counts will be smoothed using a sigmoid. This flag disables that.
     #[clap(long)]
     no_sigmoid: bool,
 
+    /// Weigh how likely each word is to be the answer with a prior learned by the `fit-prior`
+    /// command, instead of with a sigmoid of its count.
+    #[clap(long, conflicts_with = "no_sigmoid")]
+    prior: Option<PathBuf>,
+
     /// Set how candidates are ranked at each step of the solver.
     ///
     /// By default, candidates will be ranked based on expected score.
     #[clap(short, long, value_enum, default_value = "expected-score")]
     rank_by: Rank,
 
+    /// The estimate of how many more guesses it takes to find the answer given the entropy
+    /// left, which ranking by expected score relies on, as `shape:slope:intercept`.
+    ///
+    /// The shape is one of linear, log, exp, inverse, square, or sqrt. The `fit` command finds
+    /// good ones. Defaults to `log:3.87:3.679`.
+    #[clap(long)]
+    steps_model: Option<roget::StepsModel>,
+
+    /// How many guesses ahead to look when ranking by expected score.
+    ///
+    /// At each step beyond the first, only the best --width guesses are looked further ahead,
+    /// and at most --budget positions are looked at per guess.
+    #[clap(long, default_value_t = 1)]
+    depth: usize,
+
+    /// How many guesses to look further ahead at each step when --depth is more than 1.
+    #[clap(long)]
+    width: Option<usize>,
+
+    /// How many positions may be looked at per guess when --depth is more than 1.
+    #[clap(long)]
+    budget: Option<usize>,
+
     /// By default, correcness computation are cached. This flag disables that.
     /// #[allow(warn(deprecated))]
     #[clap(long)]
@@ -43,11 +100,233 @@ struct Args {
     #[clap(short, long, conflicts_with = "interactive")]
     games: Option<usize>,
 
+    /// The number of games to play at once.
+    ///
+    /// Defaults to the number of cores. The results are the same however many games are played at
+    /// once.
+    #[clap(short, long, conflicts_with_all = ["interactive", "adversarial"])]
+    jobs: Option<NonZeroUsize>,
+
+    /// The number of guesses after which a game counts as lost.
+    ///
+    /// Defaults to 6, or to 5 more than the number of boards with --boards. Jotto, Fibble, and
+    /// --adversarial have no fixed limit, so they default to 32, which leaves the score
+    /// distribution intact.
+    #[clap(long)]
+    max_guesses: Option<usize>,
+
+    /// Only make guesses that can be shown to still find the answer within --max-guesses,
+    /// whatever it is, even if that makes for a worse average.
+    ///
+    /// Consider using `--rank-by minimax` with this.
+    #[clap(long, conflicts_with = "lies")]
+    guarantee: bool,
+
+    /// Play games with this many boards at once, like Dordle (2), Quordle (4), or Octordle (8).
+    ///
+    /// Every guess is scored against all boards. Pass a comma-separated list, like `2,4,8`, to
+    /// benchmark each number of boards in turn.
+    #[clap(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "1",
+        conflicts_with = "interactive"
+    )]
+    boards: Vec<usize>,
+
+    /// Play against an adversarial host, like Absurdle, that dodges the guesses for as long as it
+    /// can, and report how many guesses it takes to corner it.
+    ///
+    /// Consider using `--rank-by minimax` with this.
+    #[clap(long, conflicts_with_all = ["games", "interactive", "boards"])]
+    adversarial: bool,
+
     /// Launch in interactive mode.
     ///
     /// This mode is intended for helping you play the game elsewhere. The program will tell you what word to guess next, and ask you for what combination of correct/misplaced/incorrect you got in return.
+    ///
+    /// You may also play a different word than the suggested one. Unless `--easy` is passed, that word is checked against the official hard mode rules first.
     #[clap(short, long, conflicts_with = "games")]
     interactive: bool,
+
+    /// Append a transcript of every game to this file, as one line of JSON per game.
+    ///
+    /// Only single-board games are recorded. Use the `replay` command to check the recorded
+    /// guesses against the solver.
+    #[clap(long, conflicts_with = "adversarial")]
+    transcript: Option<PathBuf>,
+
+    /// The game to play: Wordle, or Jotto, where a guess is only told how many letters it has in
+    /// common with the answer.
+    ///
+    /// Jotto can only be benchmarked, with a single board.
+    #[clap(
+        long,
+        value_enum,
+        default_value = "wordle",
+        conflicts_with_all = ["interactive", "adversarial", "transcript"]
+    )]
+    feedback: Feedback,
+
+    /// Play Fibble, where this many tiles of the feedback for every guess are shown in the wrong
+    /// color, and report the average score over the answers.
+    ///
+    /// Fibble itself has one lie per row. Fibble can only be benchmarked, with a single board.
+    #[clap(
+        long,
+        conflicts_with_all = ["interactive", "adversarial", "transcript", "feedback"]
+    )]
+    lies: Option<usize>,
+
+    /// Open with the best word found by looking this many guesses (1 or 2) ahead under the other
+    /// options, instead of the built-in opener.
+    ///
+    /// This takes a while for large dictionaries, so the result is cached on disk; see
+    /// --opener-cache.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+    opener_plies: Option<u8>,
+
+    /// The directory that computed openers are cached in.
+    ///
+    /// Defaults to `roget` in `$XDG_CACHE_HOME` or `~/.cache`, if either can be found.
+    #[clap(long)]
+    opener_cache: Option<PathBuf>,
+
+    /// The directory that the outcome of every word guessed against every other is kept in, so
+    /// that it only has to be computed once for each dictionary.
+    ///
+    /// Defaults to the same directory as --opener-cache.
+    #[clap(long, conflicts_with = "no_cache")]
+    pattern_cache: Option<PathBuf>,
+
+    /// Check every outcome in the --pattern-cache file against its checksum before using it,
+    /// rather than only its header.
+    #[clap(long, conflicts_with = "no_cache")]
+    verify_pattern_cache: bool,
+
+    /// Play from a tree of guesses, as written by the `tree` command, instead of running the
+    /// solver.
+    #[clap(
+        long,
+        conflicts_with_all = ["interactive", "adversarial", "transcript", "feedback", "lies"]
+    )]
+    tree: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Feed the games of a transcript back through the solver, and report every guess that
+    /// differs from what the solver would have guessed.
+    ///
+    /// The solver uses the options recorded in the transcript if there are any, and the ones
+    /// given on the command line otherwise.
+    Replay {
+        /// A transcript, as written by --transcript.
+        path: PathBuf,
+    },
+
+    /// Work out which words could have been guessed to produce each row of a share grid, like
+    /// the 🟩🟨⬛ one people post after playing.
+    Grid {
+        /// A file holding the grid. If not given, the grid is read from standard input.
+        path: Option<PathBuf>,
+
+        /// The answer of the game the grid is from, if it is known.
+        #[clap(long)]
+        answer: Option<String>,
+
+        /// How many of the most likely words to list for each row.
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Rank every word as the first guess under the given options, and print the best ones along
+    /// with their scores (higher is better).
+    ///
+    /// The ranking is cached on disk; see --opener-cache.
+    Openers {
+        /// How many openers to print.
+        #[clap(long, default_value_t = 10)]
+        top: usize,
+
+        /// How many guesses (1 or 2) to look ahead when ranking.
+        #[clap(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=2))]
+        plies: u8,
+    },
+
+    /// Find the strategy that takes the fewest guesses in total over every answer, and print
+    /// its total and average number of guesses.
+    ///
+    /// Honors --easy and --max-guesses. This can take hours for a full dictionary, so progress
+    /// can be saved to a checkpoint to pick up from later.
+    Exact {
+        /// Save progress to this file, and pick up from it if it exists already.
+        #[clap(long)]
+        checkpoint: Option<PathBuf>,
+
+        /// How often to save progress to the checkpoint, in seconds.
+        #[clap(long, default_value_t = 60, requires = "checkpoint")]
+        every: u64,
+
+        /// Only try this many of the most promising guesses in each position. This is much
+        /// faster, but the strategy may then fall short of optimal.
+        #[clap(long)]
+        shortlist: Option<usize>,
+
+        /// Also write the strategy to this file, in the same form as the `tree` command.
+        #[clap(long)]
+        tree: Option<PathBuf>,
+    },
+
+    /// Play every answer with the solver, and write down the guesses it makes as a tree, one
+    /// line per answer, like `salet BBBBB1 courd BYBBB2 nymph GGGGG3`.
+    ///
+    /// Use --tree to play from the tree later on, which makes every guess instant.
+    Tree {
+        /// The file to write the tree to. If not given, it is written to standard output.
+        path: Option<PathBuf>,
+    },
+
+    /// Learn how likely each word is to be the answer from which words of the dictionary are
+    /// answers, and how common they are, how they end, and which letters they have.
+    ///
+    /// Pass the result to --prior to play with it.
+    FitPrior {
+        /// The file to write the prior to. If not given, it is written to standard output.
+        path: Option<PathBuf>,
+    },
+
+    /// Fit the estimate of how many more guesses it takes to find the answer given the entropy
+    /// left, which ranking by expected score relies on.
+    ///
+    /// Every shape of model is fitted to how many guesses the solver took from each entropy, and
+    /// benchmarked by playing every answer (up to --games) with it. The games played with the
+    /// best one are then fitted to again, --rounds times in all. Pass the result to
+    /// --steps-model to play with it.
+    Fit {
+        /// Fit to these files in the first round, with the entropy and the number of guesses
+        /// on every line, like `entropy.dat`. If none are given, the data comes from playing
+        /// with --steps-model.
+        logs: Vec<PathBuf>,
+
+        /// The number of parts the data is split into to cross-validate each fit.
+        #[clap(long, default_value_t = 5)]
+        folds: usize,
+
+        /// How many times to fit and benchmark.
+        #[clap(long, default_value_t = 1)]
+        rounds: usize,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Feedback {
+    Wordle,
+    Jotto,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -66,12 +345,49 @@ enum Rank {
 
     /// E[information]
     ExpectedInformation,
+
+    /// -(number of candidates left in the worst case)
+    Minimax,
+
+    /// P(solved within --max-guesses)
+    WinProbability,
 }
 
 fn main() {
     let args = Args::parse();
 
-    let mut solver = Solver::builder();
+    let mut dictionary = if let Some(path) = &args.dictionary {
+        match Dictionary::load(path) {
+            Ok(dictionary) => dictionary.leak(),
+            Err(e) => {
+                eprintln!("could not load dictionary '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(dictionary) = Dictionary::builtin(args.length) {
+        dictionary
+    } else {
+        let lengths: Vec<_> = Dictionary::builtin_lengths()
+            .map(|l| l.to_string())
+            .collect();
+        eprintln!(
+            "there is no dictionary for {}-letter words (available: {})",
+            args.length,
+            lengths.join(", ")
+        );
+        std::process::exit(1);
+    };
+    if let Some(path) = &args.answers {
+        dictionary = match dictionary.load_answers(path) {
+            Ok(dictionary) => dictionary.leak(),
+            Err(e) => {
+                eprintln!("could not load answers '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    let mut solver = Solver::builder(dictionary);
     if args.no_cache {
         solver.cache = false;
     }
@@ -81,105 +397,851 @@ fn main() {
     if args.no_sigmoid {
         solver.sigmoid = false;
     }
+    if let Some(path) = &args.prior {
+        match roget::Prior::load(path) {
+            Ok(prior) => solver.prior = Some(prior.leak()),
+            Err(e) => {
+                eprintln!("could not load prior '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
     if args.easy {
         solver.hard_mode = false;
     }
+    let max_guesses = |boards| {
+        args.max_guesses
+            .unwrap_or_else(|| default_max_guesses(&args, boards))
+    };
+    solver.max_guesses = max_guesses(1);
+    solver.guarantee = args.guarantee;
+    solver.lookahead.depth = args.depth;
+    if let Some(width) = args.width {
+        solver.lookahead.width = width;
+    }
+    if let Some(budget) = args.budget {
+        solver.lookahead.budget = budget;
+    }
+    if let Some(lies) = args.lies {
+        if lies > dictionary.word_length() {
+            eprintln!(
+                "{}-letter words can't have {} lies per guess",
+                dictionary.word_length(),
+                lies
+            );
+            std::process::exit(1);
+        }
+        solver.lies = lies;
+    }
     solver.rank_by = match args.rank_by {
         Rank::First => roget::Rank::First,
         Rank::ExpectedScore => roget::Rank::ExpectedScore,
         Rank::WeightedInformation => roget::Rank::WeightedInformation,
         Rank::InfoPlusProbability => roget::Rank::InfoPlusProbability,
         Rank::ExpectedInformation => roget::Rank::ExpectedInformation,
+        Rank::Minimax => roget::Rank::Minimax,
+        Rank::WinProbability => roget::Rank::WinProbability,
+    };
+    if let Some(model) = args.steps_model {
+        solver.steps_model = model;
+    }
+    let needs_solver = !matches!(
+        args.command,
+        Some(Command::Exact { .. } | Command::Grid { .. } | Command::FitPrior { .. })
+    );
+    if solver.cache && needs_solver {
+        if let Some(dir) = args.pattern_cache.clone().or_else(default_cache_dir) {
+            let verify = args.verify_pattern_cache;
+            let loaded = match args.feedback {
+                Feedback::Wordle if verify => solver.verify_pattern_matrix(&dir),
+                Feedback::Wordle => solver.load_pattern_matrix(&dir),
+                Feedback::Jotto if verify => solver
+                    .with_feedback::<roget::JottoFeedback>()
+                    .verify_pattern_matrix(&dir),
+                Feedback::Jotto => solver
+                    .with_feedback::<roget::JottoFeedback>()
+                    .load_pattern_matrix(&dir),
+            };
+            // The matrix is used even if it couldn't be saved, so this isn't fatal.
+            if let Err(e) = loaded {
+                eprintln!("could not save the pattern matrix: {}", e);
+            }
+        }
+    }
+    let opener_cache = args.opener_cache.clone().or_else(default_cache_dir);
+    if let Some(plies) = args.opener_plies {
+        check_openers(solver.rank_by, solver.lies);
+        solver = match solver.with_computed_opener(usize::from(plies), opener_cache.as_deref()) {
+            Ok(solver) => solver,
+            Err(e) => {
+                eprintln!("could not compute the opener: {}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+    let mut config = GameConfig::default();
+    config.max_guesses = max_guesses(1);
+    config.hard_mode = !args.easy;
+    let tree = args
+        .tree
+        .as_ref()
+        .map(|path| match roget::Strategy::load(dictionary, path) {
+            Ok(strategy) => strategy,
+            Err(e) => {
+                eprintln!("could not load tree '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        });
+    match &args.command {
+        Some(Command::Openers { top, plies }) => {
+            check_openers(solver.rank_by, solver.lies);
+            match solver.rank_openers(usize::from(*plies), *top, opener_cache.as_deref()) {
+                Ok(openers) => {
+                    for (word, score) in openers {
+                        println!("{} {:.4}", word, score);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("could not rank openers: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Exact {
+            checkpoint,
+            every,
+            shortlist,
+            tree,
+        }) => {
+            if args.feedback != Feedback::Wordle || solver.lies != 0 {
+                eprintln!("exact strategies can only be found for plain Wordle");
+                std::process::exit(1);
+            }
+            exact(
+                solver,
+                max_guesses(1),
+                checkpoint.as_deref(),
+                Duration::from_secs(*every),
+                *shortlist,
+                tree.as_deref(),
+            );
+            return;
+        }
+        Some(Command::Tree { path }) => {
+            let w = roget::Wordle::with_dictionary(dictionary).with_config(config);
+            let strategy = w.record_strategy(|| solver.build());
+            write_tree(&strategy, dictionary, path.as_deref());
+            eprintln!(
+                "found {} of {} answers in {} guesses",
+                strategy.answers(),
+                dictionary.answers().len(),
+                strategy.total()
+            );
+            return;
+        }
+        Some(Command::FitPrior { path }) => {
+            fit_prior(dictionary, path.as_deref());
+            return;
+        }
+        Some(Command::Fit {
+            logs,
+            folds,
+            rounds,
+        }) => {
+            if args.feedback != Feedback::Wordle
+                || solver.lies != 0
+                || solver.rank_by != roget::Rank::ExpectedScore
+            {
+                eprintln!(
+                    "the steps model can only be fitted for plain Wordle, ranked by expected score"
+                );
+                std::process::exit(1);
+            }
+            if *folds < 2 {
+                eprintln!("cross-validation needs at least 2 folds");
+                std::process::exit(1);
+            }
+            let w = roget::Wordle::with_dictionary(dictionary).with_config(config);
+            thread_pool(args.jobs).install(|| fit(solver, &w, args.games, logs, *folds, *rounds));
+            return;
+        }
+        Some(Command::Replay { path }) => {
+            replay(path, solver);
+            return;
+        }
+        Some(Command::Grid { path, answer, top }) => {
+            grid(path.as_deref(), answer.as_deref(), *top, dictionary);
+            return;
+        }
+        None => {}
+    }
+
+    let mut transcript = args.transcript.as_ref().map(|path| {
+        match std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+        {
+            Ok(f) => std::io::BufWriter::new(f),
+            Err(e) => {
+                eprintln!("could not open transcript '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    });
+    let mut record = |answer: Option<&str>, history: &[roget::Guess]| {
+        if let Some(f) = &mut transcript {
+            let t = Transcript::new(answer, history, dictionary, Some(solver));
+            if let Err(e) = t.write(&mut *f).and_then(|_| f.flush()) {
+                eprintln!("could not write transcript: {}", e);
+                std::process::exit(1);
+            }
+        }
     };
+
     if args.interactive {
-        play_interactive(solver.build());
+        let (answer, history) = play_interactive(solver.build(), dictionary, config);
+        record(answer.as_deref(), &history);
+    } else if args.adversarial {
+        let w = roget::Wordle::with_dictionary(dictionary).with_config(config);
+        match w.play_adversarial(solver.build()) {
+            Ok(GameResult::Won(s)) => eprintln!("cornered the adversary in {} guesses", s),
+            Ok(GameResult::Lost) => eprintln!("failed to corner the adversary"),
+            Err(e) => eprintln!("broke the rules against the adversary: {}", e),
+        }
+    } else {
+        thread_pool(args.jobs).install(|| {
+            for &boards in &args.boards {
+                if args.boards.len() > 1 {
+                    eprintln!("{} board(s):", boards);
+                }
+                let mut solver = solver;
+                solver.max_guesses = max_guesses(boards);
+                let mut config = config;
+                config.max_guesses = max_guesses(boards);
+                let w = roget::Wordle::with_dictionary(dictionary).with_config(config);
+                if args.feedback == Feedback::Jotto {
+                    if boards != 1 {
+                        eprintln!("Jotto can only be played with a single board");
+                        std::process::exit(1);
+                    }
+                    let solver = solver.with_feedback::<roget::JottoFeedback>();
+                    play_scored(dictionary, args.games, |answer| {
+                        w.play_scored(answer, solver.build())
+                    });
+                } else if let Some(lies) = args.lies {
+                    if boards != 1 {
+                        eprintln!("Fibble can only be played with a single board");
+                        std::process::exit(1);
+                    }
+                    play_scored(dictionary, args.games, |answer| {
+                        w.play_fibble(answer, lies, solver.build())
+                    });
+                } else if let Some(strategy) = &tree {
+                    if boards != 1 {
+                        eprintln!("a tree can only be played with a single board");
+                        std::process::exit(1);
+                    }
+                    play(
+                        || roget::TreeGuesser::new(dictionary, strategy),
+                        &w,
+                        args.games,
+                        |_| {},
+                    );
+                } else if boards == 1 {
+                    play(
+                        || solver.build(),
+                        &w,
+                        args.games,
+                        |outcome| record(Some(outcome.answer), &outcome.transcript),
+                    );
+                } else {
+                    play_multi(|| solver.build_multi(boards), &w, boards, args.games);
+                }
+            }
+        });
+    }
+}
+
+/// The number of guesses a game allows when --max-guesses isn't passed.
+fn default_max_guesses(args: &Args, boards: usize) -> usize {
+    if args.feedback == Feedback::Jotto || args.lies.is_some() || args.adversarial {
+        32
+    } else if boards > 1 {
+        boards + 5
     } else {
-        play(move || solver.build(), args.games);
+        6
     }
 }
 
-fn play_interactive(mut guesser: impl Guesser) {
-    let mut history = Vec::with_capacity(6);
+/// Finds the optimal strategy for `solver`'s dictionary and prints how it does.
+fn exact(
+    solver: roget::Options,
+    max_guesses: usize,
+    checkpoint: Option<&Path>,
+    every: Duration,
+    shortlist: Option<usize>,
+    tree: Option<&Path>,
+) {
+    let mut exact = solver.build_exact().with_max_guesses(max_guesses);
+    if let Some(n) = shortlist {
+        exact = exact.with_shortlist(n);
+    }
+    if let Some(path) = checkpoint {
+        exact = match exact.with_checkpoint(path, every) {
+            Ok(exact) => exact,
+            Err(e) => {
+                eprintln!("could not load checkpoint '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        };
+    }
+    match exact.solve() {
+        Ok(Some(solution)) => {
+            println!(
+                "{} guesses over {} answers (average: {:.4})",
+                solution.total,
+                solution.games,
+                solution.average()
+            );
+            if solution.is_optimal() {
+                println!("this is optimal");
+            } else {
+                println!("no strategy takes fewer than {}", solution.lower_bound);
+            }
+            if let Some(path) = tree {
+                write_tree(&solution.strategy, solver.dictionary, Some(path));
+            }
+        }
+        Ok(None) => {
+            println!(
+                "no strategy finds every answer within {} guesses",
+                max_guesses
+            );
+        }
+        Err(e) => {
+            eprintln!("could not save checkpoint: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes `strategy` to `path`, or to standard output if there is none.
+fn write_tree(strategy: &roget::Strategy, dictionary: &Dictionary, path: Option<&Path>) {
+    let written = match path {
+        Some(path) => std::fs::File::create(path).and_then(|f| {
+            let mut f = std::io::BufWriter::new(f);
+            strategy.write(dictionary, &mut f)?;
+            f.flush()
+        }),
+        None => strategy.write(dictionary, std::io::stdout().lock()),
+    };
+    if let Err(e) = written {
+        eprintln!("could not write tree: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// A pool of `jobs` threads, or of one per core. The solver spreads its own work over the pool
+/// too, so --jobs bounds that as well.
+fn thread_pool(jobs: Option<NonZeroUsize>) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.map_or(0, NonZeroUsize::get))
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("could not start the thread pool: {}", e);
+            std::process::exit(1);
+        })
+}
+
+/// Fits every shape of steps model to `logs`, or to games played with `solver` if there are
+/// none, benchmarks each one on the answers of `w` (up to `max`), and fits again to the games of
+/// the best one, `rounds` times in all.
+fn fit(
+    mut solver: roget::Options,
+    w: &roget::Wordle,
+    max: Option<usize>,
+    logs: &[PathBuf],
+    folds: usize,
+    rounds: usize,
+) {
+    let mut data = if logs.is_empty() {
+        eprintln!("playing with {} to fit to", solver.steps_model);
+        benchmark(solver, w, max).data
+    } else {
+        let mut data = Vec::new();
+        for path in logs {
+            match roget::StepsModel::load_data(path) {
+                Ok(d) => data.extend(d),
+                Err(e) => {
+                    eprintln!("could not load '{}': {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        data
+    };
+    for round in 1..=rounds {
+        if data.len() < folds {
+            eprintln!(
+                "{} data points are too few to fit with {} folds",
+                data.len(),
+                folds
+            );
+            std::process::exit(1);
+        }
+        eprintln!("round {}: fitting to {} data points", round, data.len());
+        let mut best: Option<Benchmark> = None;
+        for shape in roget::Shape::ALL {
+            let model = roget::StepsModel::fit(shape, &data);
+            let error = roget::StepsModel::cross_validate(shape, &data, folds);
+            solver.steps_model = model;
+            let result = benchmark(solver, w, max);
+            println!(
+                "E[guesses] = {:<40} rmse {:.4}  average score {:.4}  lost {}",
+                model.formula(),
+                error,
+                result.average,
+                result.losses
+            );
+            // Fewer lost games wins over a better average, and the earlier shape wins ties.
+            if best
+                .as_ref()
+                .is_none_or(|b| (result.losses, result.average) < (b.losses, b.average))
+            {
+                best = Some(result);
+            }
+        }
+        let best = best.expect("there is always a shape to fit");
+        eprintln!(
+            "best: --steps-model {} (average score {:.4})",
+            best.model, best.average
+        );
+        solver.steps_model = best.model;
+        data = best.data;
+    }
+    println!("{}", solver.steps_model);
+}
+
+/// How a steps model did over a benchmark, and the data logged while playing with it.
+struct Benchmark {
+    model: roget::StepsModel,
+    average: f64,
+    losses: usize,
+    data: Vec<(f64, usize)>,
+}
+
+/// Plays every answer of `w` (up to `max`) with `solver`, spread over the current thread pool,
+/// and logs the entropy left before each guess, and how many guesses it took from there.
+fn benchmark(solver: roget::Options, w: &roget::Wordle, max: Option<usize>) -> Benchmark {
+    let answers = w.dictionary().answers();
+    let games: Vec<(Option<usize>, Vec<_>)> = answers
+        [..max.unwrap_or(usize::MAX).min(answers.len())]
+        .par_iter()
+        .map(|&answer| {
+            let log = RefCell::new(Vec::new());
+            let outcome = w.play_ids(
+                answer,
+                Logged {
+                    solver: solver.build(),
+                    log: &log,
+                },
+            );
+            (outcome.score(), log.into_inner())
+        })
+        .collect();
+    let mut score = 0;
+    let mut won = 0;
+    let mut data = Vec::new();
+    for (result, log) in &games {
+        if let Some(s) = result {
+            score += s;
+            won += 1;
+        }
+        data.extend_from_slice(log);
+    }
+    Benchmark {
+        model: solver.steps_model,
+        average: score as f64 / won as f64,
+        losses: games.len() - won,
+        data,
+    }
+}
+
+/// A solver that keeps what `Solver::steps_left` says about the game it played.
+struct Logged<'a> {
+    solver: Solver,
+    log: &'a RefCell<Vec<(f64, usize)>>,
+}
+
+impl IdGuesser for Logged<'_> {
+    fn dictionary(&self) -> &'static Dictionary {
+        IdGuesser::dictionary(&self.solver)
+    }
+
+    fn guess_id(&mut self, history: &[roget::IdGuess]) -> roget::WordId {
+        self.solver.guess_id(history)
+    }
+
+    fn finish(&self, guesses: usize) {
+        self.log
+            .borrow_mut()
+            .extend(self.solver.steps_left(guesses));
+    }
+}
+
+/// Learns a prior from `dictionary`, writes it to `path`, and reports how well it fits.
+fn fit_prior(dictionary: &Dictionary, path: Option<&Path>) {
+    if dictionary.answers().len() == dictionary.len() {
+        eprintln!("every word of the dictionary is an answer, so there is nothing to learn from");
+        std::process::exit(1);
+    }
+    let prior = roget::Prior::fit(dictionary);
+    let written = match path {
+        Some(path) => std::fs::File::create(path).and_then(|f| prior.write(f)),
+        None => prior.write(std::io::stdout().lock()),
+    };
+    if let Err(e) = written {
+        eprintln!("could not write prior: {}", e);
+        std::process::exit(1);
+    }
+    for (name, weight) in prior.weights() {
+        eprintln!("{:>16}: {:.4}", name, weight);
+    }
+    eprintln!(
+        "log loss: {:.4} (with the sigmoid of counts: {:.4})",
+        prior.log_loss(dictionary),
+        roget::Prior::sigmoid_log_loss(dictionary)
+    );
+}
+
+/// `$XDG_CACHE_HOME/roget`, or `~/.cache/roget` if that isn't set.
+fn default_cache_dir() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".cache")))?;
+    Some(cache.join("roget"))
+}
+
+/// Exits if openers can't be ranked with these options.
+fn check_openers(rank_by: roget::Rank, lies: usize) {
+    if rank_by == roget::Rank::First {
+        eprintln!("openers can't be ranked with --rank-by first");
+        std::process::exit(1);
+    }
+    if lies != 0 {
+        eprintln!("openers can't be ranked with --lies");
+        std::process::exit(1);
+    }
+}
+
+/// Plays a game at the user's side, holding them to the rules in `config`.
+///
+/// Returns the answer, if it was found, and every guess that was made.
+fn play_interactive(
+    mut guesser: impl Guesser,
+    dictionary: &'static Dictionary,
+    config: GameConfig,
+) -> (Option<String>, Vec<roget::Guess<'static>>) {
+    let mut history = Vec::with_capacity(config.max_guesses);
     println!("C: Correct / Green, M: Misplaced / Yellow, W: Wrong / Gray");
-    // Wordle only allows six guesses.
-    for _ in 1..=6 {
-        let guess = guesser.guess(&history);
-        println!("Guess:  {}", guess.to_uppercase());
+    println!("G/Y/B, 🟩/🟨/⬛, and 2/1/0 work too");
+    for _ in 1..=config.max_guesses {
+        let suggestion = guesser.guess(&history);
+        println!("Guess:  {}", suggestion.to_uppercase());
+        let guess = loop {
+            match ask_for_guess(&suggestion, dictionary, config, &history) {
+                Ok(g) => break g,
+                Err(e) => println!("{}", e),
+            }
+        };
         let correctness = {
             loop {
-                match ask_for_correctness() {
+                match ask_for_correctness(dictionary.word_length()) {
                     Ok(c) => break c,
                     Err(e) => println!("{}", e),
                 }
             }
         };
-        if correctness == [roget::Correctness::Correct; WORD_LENGTH] {
-            println!("The answer was {}", guess.to_uppercase());
-            return;
-        }
         history.push(roget::Guess {
-            word: Cow::Owned(guess),
+            word: Cow::Owned(guess.clone()),
             mask: correctness,
         });
+        if correctness.is_solved() {
+            println!("The answer was {}", guess.to_uppercase());
+            return (Some(guess), history);
+        }
     }
-    println!("Game Over, only six guesses are allowed");
+    println!("Game Over, only {} guesses are allowed", config.max_guesses);
+    (None, history)
 }
 
-fn ask_for_correctness() -> Result<[roget::Correctness; WORD_LENGTH], Cow<'static, str>> {
-    print!("Colors: ");
+/// Lets the user play a word other than the suggested one, which is checked against the rules in
+/// `config`.
+fn ask_for_guess(
+    suggestion: &str,
+    dictionary: &Dictionary,
+    config: GameConfig,
+    history: &[roget::Guess],
+) -> Result<String, Cow<'static, str>> {
+    print!("Played (leave empty for {}): ", suggestion.to_uppercase());
     std::io::Write::flush(&mut std::io::stdout()).unwrap();
-    let mut answer = String::with_capacity(7);
-    std::io::stdin().read_line(&mut answer).unwrap();
-    let answer = answer
-        .trim()
-        .chars()
-        .filter(|v| !v.is_whitespace())
-        .map(|v| v.to_ascii_uppercase())
-        .collect::<String>();
-    if answer.len() != WORD_LENGTH {
+    let mut guess = String::with_capacity(dictionary.word_length() + 2);
+    std::io::stdin().read_line(&mut guess).unwrap();
+    let guess = guess.trim().to_lowercase();
+    if guess.is_empty() {
+        return Ok(suggestion.to_string());
+    }
+    if guess.chars().count() != dictionary.word_length() {
         Err(format!(
-            "You did not provide exactly {} colors.",
-            WORD_LENGTH
+            "The word must be exactly {} letters long.",
+            dictionary.word_length()
         ))?;
     }
-    let parsed = answer
-        .chars()
-        .map(|c| match c {
-            'C' => Ok(roget::Correctness::Correct),
-            'M' => Ok(roget::Correctness::Misplaced),
-            'W' => Ok(roget::Correctness::Wrong),
-            _ => Err(format!(
-                "The guess color '{c}' wasn't recognized: use C/M/W"
-            )),
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(parsed
-        .try_into()
-        .expect("The parsed correctness is checked to be 6 items long"))
+    if config.require_dictionary && !dictionary.contains(&guess) {
+        Err(format!(
+            "{} is not in the dictionary.",
+            guess.to_uppercase()
+        ))?;
+    }
+    if config.hard_mode {
+        if let Err(violation) = roget::check_hard_mode(history, &guess) {
+            Err(format!("Not allowed in hard mode: {}.", violation))?;
+        }
+    }
+    Ok(guess)
+}
+
+fn replay(path: &Path, solver: roget::Options) {
+    let transcripts = match std::fs::File::open(path)
+        .and_then(|f| Transcript::read_all(std::io::BufReader::new(f)))
+    {
+        Ok(transcripts) => transcripts,
+        Err(e) => {
+            eprintln!("could not read transcript '{}': {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut diverged = 0;
+    for (i, transcript) in transcripts.iter().enumerate() {
+        let game = format!(
+            "game {} ({})",
+            i + 1,
+            transcript.answer.as_deref().unwrap_or("unknown answer")
+        );
+        let options = transcript
+            .options
+            .as_ref()
+            .map_or(solver, |o| o.with_dictionary(solver.dictionary));
+        match transcript.replay(options) {
+            Ok(divergences) if divergences.is_empty() => {
+                println!("{}: the solver agrees with every guess", game);
+            }
+            Ok(divergences) => {
+                diverged += 1;
+                for d in divergences {
+                    println!(
+                        "{}: guess {} was {}, but the solver would have guessed {}",
+                        game,
+                        d.turn,
+                        d.played.to_uppercase(),
+                        d.suggested.to_uppercase()
+                    );
+                }
+            }
+            Err(e) => eprintln!("{}: could not replay: {}", game, e),
+        }
+    }
+    eprintln!(
+        "the solver diverged in {} of {} games",
+        diverged,
+        transcripts.len()
+    );
+}
+
+fn grid(path: Option<&Path>, answer: Option<&str>, top: usize, dictionary: &Dictionary) {
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path),
+        None => std::io::read_to_string(std::io::stdin()),
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("could not read grid: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let analysis = match roget::parse_grid(&text)
+        .and_then(|rows| roget::analyze_grid(dictionary, &rows, answer))
+    {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            eprintln!("could not analyze grid: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if answer.is_none() {
+        println!("{} answer(s) fit the grid", analysis.answers.len());
+    }
+    for (i, row) in analysis.rows.iter().enumerate() {
+        println!(
+            "{} {}: {} word(s), likelihood {:.4}%",
+            i + 1,
+            row.mask.display(roget::Notation::Emoji),
+            row.words.len(),
+            100.0 * row.likelihood
+        );
+        for &(word, p) in row.words.iter().take(top) {
+            println!("    {} {:.2}%", word.to_uppercase(), 100.0 * p);
+        }
+    }
+}
+
+fn ask_for_correctness(length: usize) -> Result<roget::Mask, Cow<'static, str>> {
+    print!("Colors: ");
+    std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    let mut answer = String::with_capacity(4 * length + 1);
+    std::io::stdin().read_line(&mut answer).unwrap();
+    let mask: roget::Mask = answer
+        .parse()
+        .map_err(|e| format!("Those colors weren't recognized: {}", e))?;
+    if mask.len() != length {
+        Err(format!("You did not provide exactly {} colors.", length))?;
+    }
+    Ok(mask)
+}
+
+/// Plays every answer of `w` (up to `max`) with a guesser made by `mk`, spread over the current
+/// thread pool.
+///
+/// Games are reported in answer order once they are all played, so the output doesn't depend on
+/// how many are played at once.
+fn play<G>(
+    mk: impl Fn() -> G + Sync,
+    w: &roget::Wordle,
+    max: Option<usize>,
+    mut record: impl FnMut(&GameOutcome),
+) where
+    G: IdGuesser,
+{
+    let answers = w.dictionary().answers();
+    let outcomes: Vec<GameOutcome> = answers[..max.unwrap_or(usize::MAX).min(answers.len())]
+        .par_iter()
+        .map(|&answer| w.play_ids(answer, mk()))
+        .collect();
+    let mut score = 0;
+    let mut games = 0;
+    let mut losses = 0;
+    let mut histogram = Vec::new();
+    for outcome in outcomes {
+        let answer = outcome.answer;
+        record(&outcome);
+        match outcome.result {
+            Ok(GameResult::Won(s)) => {
+                games += 1;
+                score += s;
+                if s >= histogram.len() {
+                    histogram.resize(s + 1, 0);
+                }
+                histogram[s] += 1;
+                // eprintln!("guessed '{}' in {}", answer, s);
+            }
+            Ok(GameResult::Lost) => {
+                losses += 1;
+                eprintln!("failed to guess '{}'", answer);
+            }
+            Err(e) => {
+                losses += 1;
+                eprintln!("broke the rules guessing '{}': {}", answer, e);
+            }
+        }
+    }
+    print_histogram(histogram, score, games, losses);
+}
+
+/// Plays every answer of `dictionary` (up to `max`) with `play`, which returns how the game
+/// ended. Like `play`, the games are spread over the current thread pool.
+fn play_scored(
+    dictionary: &'static Dictionary,
+    max: Option<usize>,
+    play: impl Fn(&'static str) -> Result<GameResult, RuleViolation> + Sync,
+) {
+    let answers = dictionary.answers();
+    let answers = &answers[..max.unwrap_or(usize::MAX).min(answers.len())];
+    let results: Vec<_> = answers.par_iter().map(|&answer| play(answer)).collect();
+    let mut score = 0;
+    let mut games = 0;
+    let mut losses = 0;
+    let mut histogram = Vec::new();
+    for (&answer, result) in answers.iter().zip(results) {
+        match result {
+            Ok(GameResult::Won(s)) => {
+                games += 1;
+                score += s;
+                if s >= histogram.len() {
+                    histogram.resize(s + 1, 0);
+                }
+                histogram[s] += 1;
+            }
+            Ok(GameResult::Lost) => {
+                losses += 1;
+                eprintln!("failed to guess '{}'", answer);
+            }
+            Err(e) => {
+                losses += 1;
+                eprintln!("broke the rules guessing '{}': {}", answer, e);
+            }
+        }
+    }
+    print_histogram(histogram, score, games, losses);
 }
 
-fn play<G>(mut mk: impl FnMut() -> G, max: Option<usize>)
+/// Plays the answers of `w` `boards` at a time (up to `max` games) with a guesser made by `mk`.
+/// Like `play`, the games are spread over the current thread pool.
+fn play_multi<G>(mk: impl Fn() -> G + Sync, w: &roget::Wordle, boards: usize, max: Option<usize>)
 where
-    G: Guesser,
+    G: MultiGuesser,
 {
-    let w = roget::Wordle::new();
+    let sets: Vec<&[&'static str]> = w
+        .dictionary()
+        .answers()
+        .chunks_exact(boards)
+        .take(max.unwrap_or(usize::MAX))
+        .collect();
+    let results: Vec<_> = sets
+        .par_iter()
+        .map(|answers| w.play_multi(answers, mk()))
+        .collect();
     let mut score = 0;
     let mut games = 0;
+    let mut losses = 0;
     let mut histogram = Vec::new();
-    for answer in GAMES.split_whitespace().take(max.unwrap_or(usize::MAX)) {
-        let guesser = (mk)();
-        if let Some(s) = w.play(answer, guesser) {
-            games += 1;
-            score += s;
-            if s >= histogram.len() {
-                histogram.extend(std::iter::repeat(0).take(s - histogram.len() + 1));
+    for (answers, result) in sets.into_iter().zip(results) {
+        match result {
+            Ok(GameResult::Won(s)) => {
+                games += 1;
+                score += s;
+                if s >= histogram.len() {
+                    histogram.resize(s + 1, 0);
+                }
+                histogram[s] += 1;
+            }
+            Ok(GameResult::Lost) => {
+                losses += 1;
+                eprintln!("failed to guess {:?}", answers);
+            }
+            Err(e) => {
+                losses += 1;
+                eprintln!("broke the rules guessing {:?}: {}", answers, e);
             }
-            histogram[s] += 1;
-            // eprintln!("guessed '{}' in {}", answer, s);
-        } else {
-            eprintln!("failed to guess '{}'", answer);
         }
     }
+    print_histogram(histogram, score, games, losses);
+}
+
+/// Prints how many guesses the `games` that were won took, and how many of all games were lost.
+fn print_histogram(histogram: Vec<usize>, score: usize, games: usize, losses: usize) {
     let sum: usize = histogram.iter().sum();
     for (score, count) in histogram.into_iter().enumerate().skip(1) {
         let frac = count as f64 / sum as f64;
@@ -194,17 +1256,31 @@ where
         );
     }
     eprintln!("average score: {:.4}", score as f64 / games as f64);
+    eprintln!(
+        "lost {} of {} games (failure rate: {:.2}%)",
+        losses,
+        games + losses,
+        100.0 * losses as f64 / (games + losses) as f64
+    );
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn default_solver() {
-        let w = roget::Wordle::new();
-        let results: Vec<_> = crate::GAMES
-            .split_whitespace()
+        // This needs the built-in dictionary, which isn't there unless `dictionary-5.txt` and
+        // `answers-5.txt` were around at build time.
+        let Some(options) = roget::Options::builtin() else {
+            eprintln!("skipping: there is no built-in dictionary");
+            return;
+        };
+        let w = roget::Wordle::with_dictionary(options.dictionary);
+        let results: Vec<_> = w
+            .dictionary()
+            .answers()
+            .iter()
             .take(20)
-            .filter_map(|answer| w.play(answer, roget::Solver::default()))
+            .filter_map(|&answer| w.play(answer, options.build()).score())
             .collect();
 
         assert_eq!(
@@ -0,0 +1,203 @@
+use crate::{Correctness, Mask, MAX_WORD_LENGTH};
+use std::fmt;
+use std::str::FromStr;
+
+/// A way of writing down the `Correctness` of each letter of a guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// `C`orrect, `M`isplaced, and `W`rong.
+    Letters,
+
+    /// `G`reen, `Y`ellow, and `B`lack, after the colors of the tiles.
+    Colors,
+
+    /// 🟩, 🟨, and ⬛ (or ⬜), like in the grids people share after playing.
+    ///
+    /// The high contrast 🟧 and 🟦 are understood as well.
+    Emoji,
+
+    /// `2`, `1`, and `0`.
+    Digits,
+}
+
+impl Notation {
+    /// Every notation, in the order they are tried when parsing a mask.
+    pub const ALL: [Self; 4] = [Self::Letters, Self::Colors, Self::Emoji, Self::Digits];
+
+    /// The symbol for `c` in this notation.
+    pub fn symbol(self, c: Correctness) -> char {
+        use Correctness::*;
+        match (self, c) {
+            (Self::Letters, Correct) => 'C',
+            (Self::Letters, Misplaced) => 'M',
+            (Self::Letters, Wrong) => 'W',
+            (Self::Colors, Correct) => 'G',
+            (Self::Colors, Misplaced) => 'Y',
+            (Self::Colors, Wrong) => 'B',
+            (Self::Emoji, Correct) => '🟩',
+            (Self::Emoji, Misplaced) => '🟨',
+            (Self::Emoji, Wrong) => '⬛',
+            (Self::Digits, Correct) => '2',
+            (Self::Digits, Misplaced) => '1',
+            (Self::Digits, Wrong) => '0',
+        }
+    }
+
+    /// The `Correctness` that `symbol` stands for in this notation, if any.
+    ///
+    /// Letters are accepted in either case.
+    pub fn parse(self, symbol: char) -> Option<Correctness> {
+        use Correctness::*;
+        Some(match (self, symbol.to_ascii_uppercase()) {
+            (Self::Letters, 'C') => Correct,
+            (Self::Letters, 'M') => Misplaced,
+            (Self::Letters, 'W') => Wrong,
+            (Self::Colors, 'G') => Correct,
+            (Self::Colors, 'Y') => Misplaced,
+            (Self::Colors, 'B') => Wrong,
+            (Self::Emoji, '🟩' | '🟧') => Correct,
+            (Self::Emoji, '🟨' | '🟦') => Misplaced,
+            (Self::Emoji, '⬛' | '⬜') => Wrong,
+            (Self::Digits, '2') => Correct,
+            (Self::Digits, '1') => Misplaced,
+            (Self::Digits, '0') => Wrong,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Correctness::*;
+        write!(
+            f,
+            "{}/{}/{}",
+            self.symbol(Correct),
+            self.symbol(Misplaced),
+            self.symbol(Wrong)
+        )
+    }
+}
+
+/// The ways in which parsing a `Mask` can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMaskError {
+    /// There were no symbols at all.
+    Empty,
+
+    /// There were more symbols than the longest supported word has letters.
+    TooLong { len: usize },
+
+    /// A symbol isn't part of the notation the mask is written in, or of any notation if
+    /// `notation` is `None`.
+    InvalidSymbol {
+        symbol: char,
+        notation: Option<Notation>,
+    },
+}
+
+impl fmt::Display for ParseMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no colors were given"),
+            Self::TooLong { len } => write!(
+                f,
+                "{} colors were given, but words have at most {} letters",
+                len, MAX_WORD_LENGTH
+            ),
+            Self::InvalidSymbol {
+                symbol,
+                notation: Some(notation),
+            } => write!(f, "'{}' is not one of {}", symbol, notation),
+            Self::InvalidSymbol {
+                symbol,
+                notation: None,
+            } => {
+                write!(f, "'{}' was not recognized: use ", symbol)?;
+                for (i, notation) in Notation::ALL.iter().enumerate() {
+                    match i {
+                        0 => {}
+                        i if i + 1 == Notation::ALL.len() => write!(f, ", or ")?,
+                        _ => write!(f, ", ")?,
+                    }
+                    write!(f, "{}", notation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMaskError {}
+
+impl Mask {
+    /// Parses a mask written in `notation`, one symbol per letter.
+    ///
+    /// Whitespace is ignored, as are the variation selectors that some emoji come with.
+    pub fn parse_with(s: &str, notation: Notation) -> Result<Self, ParseMaskError> {
+        let mut tiles = [Correctness::Wrong; MAX_WORD_LENGTH];
+        let mut len = 0;
+        for symbol in symbols(s) {
+            let c = notation
+                .parse(symbol)
+                .ok_or(ParseMaskError::InvalidSymbol {
+                    symbol,
+                    notation: Some(notation),
+                })?;
+            if len < MAX_WORD_LENGTH {
+                tiles[len] = c;
+            }
+            len += 1;
+        }
+        if len == 0 {
+            return Err(ParseMaskError::Empty);
+        }
+        if len > MAX_WORD_LENGTH {
+            return Err(ParseMaskError::TooLong { len });
+        }
+        Ok(Self::from(&tiles[..len]))
+    }
+
+    /// Displays this mask in `notation`.
+    ///
+    /// The `Display` impl of `Mask` itself uses `Notation::Letters`.
+    pub fn display(self, notation: Notation) -> impl fmt::Display {
+        struct Displayed(Mask, Notation);
+        impl fmt::Display for Displayed {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for &c in self.0.iter() {
+                    write!(f, "{}", self.1.symbol(c))?;
+                }
+                Ok(())
+            }
+        }
+        Displayed(self, notation)
+    }
+}
+
+/// Parses a mask in any `Notation`, which is detected from its first symbol.
+impl FromStr for Mask {
+    type Err = ParseMaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let first = symbols(s).next().ok_or(ParseMaskError::Empty)?;
+        let notation = Notation::ALL
+            .into_iter()
+            .find(|notation| notation.parse(first).is_some())
+            .ok_or(ParseMaskError::InvalidSymbol {
+                symbol: first,
+                notation: None,
+            })?;
+        Self::parse_with(s, notation)
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(Notation::Letters))
+    }
+}
+
+fn symbols(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().filter(|&c| !c.is_whitespace() && c != '\u{FE0F}')
+}
@@ -1,42 +1,44 @@
-use crate::{
-    Correctness, Guess, Guesser, PackedCorrectness, DICTIONARY, FIRST_GUESS, MAX_MASK_ENUM,
-};
-use once_cell::sync::OnceCell;
+use crate::{Dictionary, Feedback, IdGuess, IdGuesser, Scored, WordId, WordleFeedback};
+use rayon::prelude::*;
 use std::borrow::Cow;
-use std::cell::Cell;
-use std::sync::Mutex;
-
-/// The initial set of words without any smoothing
-static INITIAL_COUNTS: OnceCell<Vec<(&'static str, f64, usize)>> = OnceCell::new();
-/// The initial set of words after applying sigmoid smoothing.
-static INITIAL_SIGMOID: OnceCell<Vec<(&'static str, f64, usize)>> = OnceCell::new();
-
-/// A per-thread cache of cached `Correctness` for each word pair.
-///
-/// We make this thread-local so that access to it is as cheap as we can get it.
-///
-/// We store a `Box` because the array is quite large, and we're unlikely to have the stack space
-/// needed to store the whole thing on a given thread's stack.
-type Cache = [[Cell<Option<PackedCorrectness>>; DICTIONARY.len()]; DICTIONARY.len()];
-// thread_local! {
-static COMPUTES: Mutex<Box<Cache>> = Mutex::new(Box::new());
-// }
-
-pub struct Solver {
-    remaining: Cow<'static, Vec<(&'static str, f64, usize)>>,
-    entropy: Vec<f64>,
-    options: Options,
-    last_guess_idx: Option<usize>,
-}
-
-impl Default for Solver {
-    fn default() -> Self {
-        COMPUTES;
-        Options::default().build()
-    }
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+mod exact;
+mod guarantee;
+mod lookahead;
+mod matrix;
+mod multi;
+mod opener;
+mod prior;
+mod steps;
+mod xordle;
+pub use exact::{ExactSolver, Solution};
+pub use lookahead::Lookahead;
+use matrix::Matrix;
+pub use multi::MultiSolver;
+pub use prior::Prior;
+pub use steps::{Shape, StepsModel};
+pub use xordle::XordleSolver;
+
+/// How many outcomes ranking guesses has to look at before it's worth spreading the guesses over
+/// threads.
+const PARALLEL_WORK: usize = 1 << 16;
+
+#[derive(Clone)]
+pub struct Solver<F: Feedback = WordleFeedback> {
+    remaining: Cow<'static, [(&'static str, f64, usize)]>,
+    /// Every word of the dictionary, as `remaining` was at the start.
+    initial: &'static [(&'static str, f64, usize)],
+    /// The entropy that was left before each ranked guess, along with the turn it was made on.
+    entropy: Vec<(usize, f64)>,
+    options: Options<F>,
+    cache: Option<Arc<Matrix>>,
+    /// How many outcomes can be shown for any one true outcome, given `Options::lies`.
+    lie_spread: usize,
 }
 
-// This is an estimation function for how many _more_ guesses are needed given that `entropy`
+// This is the default estimate of how many _more_ guesses are needed given that `entropy`
 // entropy remains. It was constructed by iterative regression.
 //
 // First, I logged the observed remaining entropy + remaining guesses with an implementation that
@@ -65,18 +67,55 @@ impl Default for Solver {
 // the regression that does the best also tends to overestimate the number of guesses remaining,
 // which causes the model to "go for the win" less often, and instead focus on "best information"
 // guesses.
-fn est_steps_left(entropy: f64) -> f64 {
-    // entropy * 0.2592 + 1.3202 // 3.7181
-    // (entropy * 4.066 + 3.755).ln() // 3.7172
-    // (entropy * 0.1346 + 0.2210).exp() // 3.7237
-    // 1.0 / (entropy * -0.07977 + 0.84147) // 3.7246
-    // (entropy * 0.09177 + 1.13241).powi(2) // 3.7176
-    // (entropy * 1.151 + 1.954).sqrt() // 3.7176
-    // (entropy * 3.869 + 3.679).ln() // 3.7176
-    (entropy * 3.870 + 3.679).ln() // 3.7176
+//
+// `roget fit` now does all of the above without R: it logs the data by playing every answer, fits
+// each of these shapes with cross-validation, benchmarks them, and goes again with the best one.
+// The models it finds can be played with `--steps-model`.
+impl Default for StepsModel {
+    fn default() -> Self {
+        // linear:0.2592:1.3202 // 3.7181
+        // log:4.066:3.755 // 3.7172
+        // exp:0.1346:0.2210 // 3.7237
+        // inverse:-0.07977:0.84147 // 3.7246
+        // square:0.09177:1.13241 // 3.7176
+        // sqrt:1.151:1.954 // 3.7176
+        // log:3.869:3.679 // 3.7176
+        StepsModel {
+            shape: Shape::Log,
+            slope: 3.870,
+            intercept: 3.679,
+        } // 3.7176
+    }
 }
 const PRINT_ESTIMATION: bool = false;
 
+// The probability that at most `steps` more guesses are needed given that `entropy` entropy
+// remains. `Rank::WinProbability` needs this to tell how likely a game is to go over the guess
+// limit, which the mean from a `StepsModel` can't.
+//
+// It was fitted to the same logged data (entropy.dat), with a logistic regression of whether at
+// most k more guesses were needed on the entropy for each k (see `escore-regress.r`). That gave
+// the coefficients below, as (intercept, slope). No game in the data needed more than 7.
+const STEPS_LEFT_FIT: [(f64, f64); 6] = [
+    (1.5943, -1.2707),
+    (2.9705, -0.5823),
+    (4.2705, -0.4131),
+    (5.7426, -0.3886),
+    (7.2639, -0.4011),
+    (10.5219, -0.6548),
+];
+fn p_steps_left_at_most(entropy: f64, steps: usize) -> f64 {
+    if steps > STEPS_LEFT_FIT.len() {
+        return 1.0;
+    }
+    // The regressions are independent of each other, so where there is little data they can
+    // cross. Taking the largest keeps the probabilities from going down as `steps` goes up.
+    STEPS_LEFT_FIT[..steps]
+        .iter()
+        .map(|&(intercept, slope)| 1.0 / (1.0 + (-(intercept + slope * entropy)).exp()))
+        .fold(0.0, f64::max)
+}
+
 const L: f64 = 1.0;
 // How steep is the cut-off?
 const K: f64 = 30000000.0;
@@ -110,7 +149,8 @@ fn sigmoid(p: f64) -> f64 {
 }
 const PRINT_SIGMOID: bool = false;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum Rank {
     /// Just pick the first candidate.
@@ -127,18 +167,33 @@ pub enum Rank {
 
     /// E[information]
     ExpectedInformation,
+
+    /// -(number of candidates left in the worst case)
+    Minimax,
+
+    /// P(solved within `Options::max_guesses`) = p(word) + sum over outcomes of p(outcome) *
+    /// P[guesses <= guesses left](entropy of outcome)
+    WinProbability,
 }
 
 #[derive(Debug, Clone, Copy)]
 #[non_exhaustive]
-pub struct Options {
+pub struct Options<F: Feedback = WordleFeedback> {
+    /// The words the solver may guess, and whose counts it uses as the answer prior.
+    pub dictionary: &'static Dictionary,
+
     /// If true, counts will be smoothed using a sigmoid.
     pub sigmoid: bool,
 
+    /// A learned prior for how likely each word is to be the answer, which is used instead of
+    /// `sigmoid` if given; see `Prior::fit`.
+    pub prior: Option<&'static Prior>,
+
     /// If true, candidates will be ranked based on expected score.
     pub rank_by: Rank,
 
-    /// If true, correcness computation will be cached.
+    /// If true, the outcome of every word guessed against every other is computed up front, once
+    /// per dictionary, and looked up from then on; see `load_pattern_matrix`.
     pub cache: bool,
 
     /// If true, only the most likely 1/3 of candidates are considered at each step.
@@ -146,28 +201,105 @@ pub struct Options {
 
     /// If true, solver may not guess known-wrong words.
     pub hard_mode: bool,
+
+    /// How many parts of every outcome are lies, like the one wrong tile per row of Fibble.
+    ///
+    /// When this isn't 0, candidates aren't ruled out by the outcome they would have produced,
+    /// but by whether the outcome shown could be that one with exactly this many lies in it.
+    pub lies: usize,
+
+    /// How many more guesses `Rank::ExpectedScore` expects it takes to find the answer with a
+    /// given amount of entropy left; see `StepsModel::fit`.
+    pub steps_model: StepsModel,
+
+    /// How many guesses ahead to look when ranking by `Rank::ExpectedScore`.
+    ///
+    /// Doesn't apply when there are `lies`.
+    pub lookahead: Lookahead,
+
+    /// The word to open every game with, usually one picked by `with_computed_opener`.
+    ///
+    /// If `None`, the feedback's own known good opener is used if it has one, and otherwise the
+    /// first guess is picked like any other.
+    pub opener: Option<&'static str>,
+
+    /// The number of guesses after which a game is lost, for `guarantee` and
+    /// `Rank::WinProbability`.
+    pub max_guesses: usize,
+
+    /// If true, only guesses that can be shown to find every remaining candidate within
+    /// `max_guesses` are made, even if that makes for a worse average, unless no guess can be
+    /// shown to.
+    ///
+    /// Doesn't apply when there are `lies`.
+    pub guarantee: bool,
+
+    /// How guesses are scored; see `with_feedback`.
+    pub(crate) feedback: PhantomData<F>,
 }
 
-impl Default for Options {
-    fn default() -> Self {
+impl<F: Feedback> Options<F> {
+    /// The default options for a solver that plays with the words of `dictionary`.
+    pub fn new(dictionary: &'static Dictionary) -> Self {
         Self {
+            dictionary,
             sigmoid: true,
+            prior: None,
             rank_by: Rank::ExpectedScore,
+            steps_model: StepsModel::default(),
             cache: true,
             cutoff: true,
             hard_mode: true,
+            lies: 0,
+            lookahead: Lookahead::default(),
+            opener: None,
+            max_guesses: 6,
+            guarantee: false,
+            feedback: PhantomData,
+        }
+    }
+
+    /// The default options for the built-in dictionary of the default word length, if there is
+    /// one.
+    pub fn builtin() -> Option<Self> {
+        Dictionary::builtin(crate::DEFAULT_WORD_LENGTH).map(Self::new)
+    }
+
+    /// The same options, for a solver that plays a game with different feedback.
+    pub fn with_feedback<G: Feedback>(self) -> Options<G> {
+        Options {
+            dictionary: self.dictionary,
+            sigmoid: self.sigmoid,
+            prior: self.prior,
+            rank_by: self.rank_by,
+            steps_model: self.steps_model,
+            cache: self.cache,
+            cutoff: self.cutoff,
+            hard_mode: self.hard_mode,
+            lies: self.lies,
+            lookahead: self.lookahead,
+            opener: self.opener,
+            max_guesses: self.max_guesses,
+            guarantee: self.guarantee,
+            feedback: PhantomData,
         }
     }
-}
 
-impl Options {
-    pub fn build(self) -> Solver {
-        let remaining = if self.sigmoid {
-            INITIAL_SIGMOID.get_or_init(|| {
-                let sum: usize = DICTIONARY.iter().map(|(_, count)| count).sum();
+    /// The word to open every game with, if there is one; see `opener`.
+    pub fn opener(&self) -> Option<&'static str> {
+        self.opener.or_else(|| F::opener(self.dictionary))
+    }
+
+    pub fn build(self) -> Solver<F> {
+        let dictionary = self.dictionary;
+        let remaining = if let Some(prior) = self.prior {
+            prior.initial(dictionary)
+        } else if self.sigmoid {
+            dictionary.initial_sigmoid.get_or_init(|| {
+                let sum: usize = dictionary.words().iter().map(|(_, count)| count).sum();
 
                 if PRINT_SIGMOID {
-                    for &(word, count) in DICTIONARY.iter().rev() {
+                    for &(word, count) in dictionary.words().iter().rev() {
                         let p = count as f64 / sum as f64;
                         println!(
                             "{} {:.6}% -> {:.6}% ({})",
@@ -179,7 +311,8 @@ impl Options {
                     }
                 }
 
-                DICTIONARY
+                dictionary
+                    .words()
                     .iter()
                     .copied()
                     .enumerate()
@@ -187,8 +320,9 @@ impl Options {
                     .collect()
             })
         } else {
-            INITIAL_COUNTS.get_or_init(|| {
-                DICTIONARY
+            dictionary.initial_counts.get_or_init(|| {
+                dictionary
+                    .words()
                     .iter()
                     .copied()
                     .enumerate()
@@ -197,92 +331,58 @@ impl Options {
             })
         };
 
-        if self.cache {
-            COMPUTES.with(|c| {
-                c.get_or_init(|| {
-                    // This is really silly.
-                    // We'd like to just do `Box::default()`, but that doesn't work since `Default`
-                    // isn't implemented for arbitrarily long arrays. We can't use `Box::new` since
-                    // that'll create the (huge) array on the _stack_ first before then copying it
-                    // to the heap. And support for creation of values directly on the heap (the
-                    // `box` keyword) is an unstable nightly-only feature.
-                    //
-                    // So, we use unsafe.
-
-                    // First, we sanity check that the byte value 0 is equivalent to our `None`
-                    // value.
-                    let c = &Cell::new(None::<PackedCorrectness>);
-                    println!("{:?}", c.get());
-                    assert_eq!(std::mem::size_of_val(c), 1);
-                    let c = c as *const _;
-                    let c = c as *const u16;
-                    assert_eq!(unsafe { *c }, 0);
-
-                    // Then, we allocate the number of bytes we need directly on the heap.
-                    // And we request that they're all zero, which by the above we know matches the
-                    // value we expect for `Cache`.
-                    let mem = unsafe {
-                        std::alloc::alloc_zeroed(
-                            std::alloc::Layout::from_size_align(
-                                std::mem::size_of::<Cache>(),
-                                std::mem::align_of::<Cache>(),
-                            )
-                            .unwrap(),
-                        )
-                    };
-
-                    // And then we cast it to a Box of the appropriate type, which should be safe.
-                    unsafe { Box::from_raw(mem as *mut _) }
-                });
-            });
-        }
+        let cache = if self.cache {
+            Some(Matrix::shared::<F>(dictionary))
+        } else {
+            None
+        };
+
+        let lie_spread = if self.lies == 0 {
+            1
+        } else {
+            let mut spread = 0;
+            F::for_each_lie(0, dictionary.word_length(), self.lies, |_| spread += 1);
+            assert_ne!(spread, 0, "words are too short to have that many lies");
+            spread
+        };
 
         Solver {
             remaining: Cow::Borrowed(remaining),
+            initial: remaining,
             entropy: Vec::new(),
-            last_guess_idx: None,
+            cache,
+            lie_spread,
 
             options: self,
         }
     }
 }
 
-// This inline gives about a 13% speedup.
-#[inline]
-fn get_packed(
-    row: &[Cell<Option<PackedCorrectness>>],
-    guess: &str,
-    answer: &str,
-    answer_idx: usize,
-) -> PackedCorrectness {
-    let cell = &row[answer_idx];
-    match cell.get() {
-        Some(a) => a,
-        None => {
-            let correctness = PackedCorrectness::from(Correctness::compute(answer, guess));
-            cell.set(Some(correctness));
-            correctness
-        }
+impl Solver {
+    /// The default options for a solver that plays with the words of `dictionary`; see
+    /// `Options::new`.
+    pub fn builder(dictionary: &'static Dictionary) -> Options {
+        Options::new(dictionary)
     }
 }
 
-impl Solver {
-    pub fn builder() -> Options {
-        Options::default()
+impl<F: Feedback> Solver<F> {
+    pub fn options(&self) -> &Options<F> {
+        &self.options
     }
 }
 
-impl Solver {
-    fn trim(&mut self, mut cmp: impl FnMut(&str, usize) -> bool) {
+impl<F: Feedback> Solver<F> {
+    fn trim(&mut self, mut cmp: impl FnMut(usize) -> bool) {
         if matches!(self.remaining, Cow::Owned(_)) {
             self.remaining
                 .to_mut()
-                .retain(|&(word, _, word_idx)| cmp(word, word_idx));
+                .retain(|&(_, _, word_idx)| cmp(word_idx));
         } else {
             self.remaining = Cow::Owned(
                 self.remaining
                     .iter()
-                    .filter(|(word, _, word_idx)| cmp(word, *word_idx))
+                    .filter(|&&(_, _, word_idx)| cmp(word_idx))
                     .copied()
                     .collect(),
             );
@@ -290,42 +390,44 @@ impl Solver {
     }
 }
 
-impl Guesser for Solver {
-    fn guess(&mut self, history: &[Guess]) -> String {
-        let score = history.len() as f64;
-
-        if let Some(last) = history.last() {
-            if self.options.cache {
-                let reference = PackedCorrectness::from(last.mask);
-                COMPUTES.with(|c| {
-                    let row = &c.get().unwrap()[self.last_guess_idx.unwrap()];
-                    self.trim(|word, word_idx| {
-                        reference == get_packed(row, &last.word, word, word_idx)
-                    });
-                });
-            } else {
-                self.trim(|word, _| last.matches(word));
-            }
+impl<F: Feedback> Solver<F> {
+    /// Narrows the remaining candidates down to those for which guessing the word at
+    /// `last_guess_idx` could have produced `outcome`.
+    fn observe(&mut self, last_guess_idx: usize, outcome: u32) {
+        if self.options.lies != 0 {
+            // Every true outcome can be shown as any of `lie_spread` outcomes, all equally
+            // likely. So the likelihood of `outcome` is the same for every candidate that could
+            // have produced it, and zero for the rest, which leaves the probabilities of the
+            // candidates we keep untouched.
+            let length = self.options.dictionary.word_length();
+            let mut possible = vec![false; F::outcomes(length)];
+            F::for_each_lie(outcome, length, self.options.lies, |truth| {
+                possible[truth as usize] = true
+            });
+            self.observe_any(last_guess_idx, |truth| possible[truth as usize]);
+            // A lie can make the guess look like it could still be the answer, but the game
+            // would have ended if it were.
+            self.trim(|word_idx| word_idx != last_guess_idx);
+            return;
         }
+        self.observe_any(last_guess_idx, |truth| truth == outcome);
+    }
 
-        if history.is_empty() {
-            self.last_guess_idx = Some(
-                self.remaining
-                    .iter()
-                    .find(|(word, _, _)| &**word == FIRST_GUESS)
-                    .map(|&(_, _, idx)| idx)
-                    .unwrap(),
-            );
-            // NOTE: I did a manual run with this commented out and it indeed produced "tares" as
-            // the first guess. It slows down the run by a lot though.
-            return FIRST_GUESS.to_string();
-        } else if self.options.rank_by == Rank::First || self.remaining.len() == 1 {
-            let w = self.remaining.first().unwrap();
-            self.last_guess_idx = Some(w.2);
-            return w.0.to_string();
+    /// Keeps the remaining candidates for which `possible` holds for the outcome that guessing the
+    /// word at `last_guess_idx` would have produced.
+    fn observe_any(&mut self, last_guess_idx: usize, possible: impl Fn(u32) -> bool) {
+        let dictionary = self.options.dictionary;
+        let last_guess = dictionary.encoded(last_guess_idx);
+        if let Some(cache) = self.cache.clone() {
+            let row = cache.row(last_guess_idx);
+            self.trim(|word_idx| possible(row.get(word_idx)));
+        } else {
+            self.trim(|word_idx| possible(F::compute(dictionary.encoded(word_idx), last_guess)));
         }
-        assert!(!self.remaining.is_empty());
+    }
 
+    /// The total (unnormalized) probability of the remaining candidates, and their entropy.
+    fn remaining_entropy(&self) -> (f64, f64) {
         let remaining_p: f64 = self.remaining.iter().map(|&(_, p, _)| p).sum();
         let remaining_entropy = -self
             .remaining
@@ -335,120 +437,478 @@ impl Guesser for Solver {
                 p * p.log2()
             })
             .sum::<f64>();
-        self.entropy.push(remaining_entropy);
+        (remaining_p, remaining_entropy)
+    }
 
-        let mut best: Option<Candidate> = None;
-        let mut i = 0;
-        let stop = (self.remaining.len() / 3).max(20).min(self.remaining.len());
-        let consider = if self.options.hard_mode {
-            &*self.remaining
-        } else if self.options.sigmoid {
-            INITIAL_SIGMOID.get().unwrap()
+    /// The words that may be guessed next.
+    fn consider(&self) -> &[(&'static str, f64, usize)] {
+        if self.options.hard_mode {
+            &self.remaining
         } else {
-            INITIAL_COUNTS.get().unwrap()
+            self.initial
+        }
+    }
+
+    /// The expected information gained by guessing the word at `word_idx`, and that word's
+    /// (unnormalized) probability if it is itself one of the remaining candidates.
+    ///
+    /// `totals` is scratch space that must hold `F::outcomes(word_length)` elements.
+    fn expected_information(
+        &self,
+        candidates: &impl Candidates,
+        word_idx: usize,
+        remaining_p: f64,
+        totals: &mut [f64],
+    ) -> (f64, Option<f64>) {
+        let in_remaining = candidates.fill_buckets(word_idx, totals, |p| p);
+
+        let spread = self.lie_spread as f64;
+        let sum: f64 = totals
+            .iter()
+            .copied()
+            .filter(|t| *t != 0.0)
+            .map(|p| {
+                let p_of_this_pattern = p / (remaining_p * spread);
+                p_of_this_pattern * p_of_this_pattern.log2()
+            })
+            .sum();
+
+        // What the outcome tells us about the answer is its entropy, less the entropy of the
+        // lies, which is the same whatever the answer is.
+        (-sum - spread.log2(), in_remaining)
+    }
+
+    /// The number of remaining candidates in the largest group that guessing the word at
+    /// `word_idx` could leave us with, and that word's (unnormalized) probability if it is itself
+    /// one of the remaining candidates.
+    ///
+    /// `totals` is scratch space that must hold `F::outcomes(word_length)` elements.
+    fn largest_bucket(
+        candidates: &impl Candidates,
+        word_idx: usize,
+        totals: &mut [f64],
+    ) -> (usize, Option<f64>) {
+        let in_remaining = candidates.fill_buckets(word_idx, totals, |_| 1.0);
+        let largest = totals.iter().copied().fold(0.0, f64::max);
+        (largest as usize, in_remaining)
+    }
+
+    /// How good it would be to guess the word at `word_idx` according to `Options::rank_by`, and
+    /// that word's (unnormalized) probability if it is itself one of the remaining candidates.
+    ///
+    /// Higher is better.
+    ///
+    /// `totals` and `plogp` are scratch space that must hold `F::outcomes(word_length)` elements
+    /// each.
+    fn evaluate(
+        &self,
+        word_idx: usize,
+        score: f64,
+        remaining: (f64, f64),
+        totals: &mut [f64],
+        plogp: &mut [f64],
+    ) -> (f64, Option<f64>) {
+        self.evaluate_against(self, word_idx, score, remaining, totals, plogp)
+    }
+
+    /// Like `evaluate`, but against `candidates` rather than the remaining candidates.
+    fn evaluate_against(
+        &self,
+        candidates: &impl Candidates,
+        word_idx: usize,
+        score: f64,
+        (remaining_p, remaining_entropy): (f64, f64),
+        totals: &mut [f64],
+        plogp: &mut [f64],
+    ) -> (f64, Option<f64>) {
+        if self.options.rank_by == Rank::Minimax {
+            let (largest, in_remaining) = Self::largest_bucket(candidates, word_idx, totals);
+            return (minimax(largest, in_remaining.is_some()), in_remaining);
+        }
+        if self.options.rank_by == Rank::WinProbability {
+            return self.win_probability(candidates, word_idx, score, remaining_p, totals, plogp);
+        }
+
+        let (e_info, in_remaining) =
+            self.expected_information(candidates, word_idx, remaining_p, totals);
+        let p_word = if let Some(count) = in_remaining {
+            count / remaining_p
+        } else {
+            // TODO: penalize further.
+            0.0
         };
-        for &(word, count, word_idx) in consider {
-            // considering a world where we _did_ guess `word` and got `pattern` as the
-            // correctness. now, compute what _then_ is left.
-
-            // Rather than iterate over the patterns sequentially and add up the counts of words
-            // that result in that pattern, we can instead keep a running total for each pattern
-            // simultaneously by storing them in an array. We can do this since each candidate-word
-            // pair deterministically produces only one mask.
-            let mut totals = [0.0f64; MAX_MASK_ENUM];
-
-            let mut in_remaining = false;
-            if self.options.cache {
-                COMPUTES.with(|c| {
-                    let row = &c.get().unwrap()[word_idx];
-                    for (candidate, count, candidate_idx) in &*self.remaining {
-                        in_remaining |= word_idx == *candidate_idx;
-                        let idx = get_packed(row, word, candidate, *candidate_idx);
-                        totals[usize::from(u16::from(idx))] += count;
-                    }
-                });
-            } else {
-                for (candidate, count, candidate_idx) in &*self.remaining {
-                    in_remaining |= word_idx == *candidate_idx;
-                    let idx = PackedCorrectness::from(Correctness::compute(candidate, word));
-                    totals[usize::from(u16::from(idx))] += count;
-                }
-            }
+        let goodness = goodness(
+            self.options.rank_by,
+            self.options.steps_model,
+            score,
+            p_word,
+            e_info,
+            remaining_entropy,
+            self.options.max_guesses,
+        );
+        (goodness, in_remaining)
+    }
+
+    /// The probability of finding the answer within `Options::max_guesses` if the word at
+    /// `word_idx` is guessed after `score` guesses, and that word's (unnormalized) probability if
+    /// it is itself one of the remaining candidates.
+    ///
+    /// Unlike `goodness`, this looks at the entropy left after each outcome on its own, since a
+    /// single outcome that leaves a lot to be found can lose a game that the average wouldn't.
+    ///
+    /// `totals` and `plogp` are scratch space that must hold `F::outcomes(word_length)` elements
+    /// each.
+    fn win_probability(
+        &self,
+        candidates: &impl Candidates,
+        word_idx: usize,
+        score: f64,
+        remaining_p: f64,
+        totals: &mut [f64],
+        plogp: &mut [f64],
+    ) -> (f64, Option<f64>) {
+        let length = self.options.dictionary.word_length();
+        // The guesses that are left after this one.
+        let left = self.options.max_guesses.saturating_sub(score as usize + 1);
+        let in_remaining = candidates.fill_buckets(word_idx, totals, |p| p);
+        candidates.fill_buckets(word_idx, plogp, |p| p * p.log2());
+
+        let spread = self.lie_spread as f64;
+        let p_word = in_remaining.map_or(0.0, |count| count / remaining_p);
+        let p_later: f64 = totals
+            .iter()
+            .zip(&*plogp)
+            .enumerate()
+            .filter(|&(outcome, (&t, _))| t != 0.0 && !F::is_solved(outcome as u32, length))
+            .map(|(_, (&t, &plogp))| {
+                let entropy = t.log2() - plogp / t;
+                t / (remaining_p * spread) * p_steps_left_at_most(entropy, left)
+            })
+            .sum();
+        (p_word + p_later, in_remaining)
+    }
+}
+
+/// How good a guess is by `Rank::Minimax`, given the number of remaining candidates in the largest
+/// group it could leave us with, and whether it is itself one of the remaining candidates.
+///
+/// Among guesses with equally large worst cases, the ones that might just win are better.
+fn minimax(largest: usize, might_win: bool) -> f64 {
+    let bonus = if might_win { 0.5 } else { 0.0 };
+    bonus - largest as f64
+}
+
+/// How many of `candidates` remaining candidates have to be considered as guesses with
+/// `Options::cutoff`: a third of them, but at least 20.
+fn cutoff(candidates: usize) -> usize {
+    (candidates / 3).max(20).min(candidates)
+}
+
+/// How good it would be to guess a word that is the answer with probability `p_word`, and that is
+/// expected to yield `e_info` bits of information, after `score` guesses have been made already.
+///
+/// Higher is better.
+fn goodness(
+    rank_by: Rank,
+    steps_model: StepsModel,
+    score: f64,
+    p_word: f64,
+    e_info: f64,
+    remaining_entropy: f64,
+    max_guesses: usize,
+) -> f64 {
+    match rank_by {
+        Rank::First => unreachable!("the first candidate is picked without ranking"),
+        Rank::Minimax => unreachable!("minimax doesn't look at probabilities"),
+        Rank::ExpectedScore => {
+            // NOTE: Higher is better, so we negate the result.
+            -(p_word * (score + 1.0)
+                + (1.0 - p_word) * (score + steps_model.estimate(remaining_entropy - e_info)))
+        }
+        Rank::WeightedInformation => p_word * e_info,
+        Rank::InfoPlusProbability => p_word + e_info,
+        Rank::ExpectedInformation => e_info,
+        Rank::WinProbability => {
+            // Without the outcomes on hand, all we can go by is the entropy expected to be left.
+            let left = max_guesses.saturating_sub(score as usize + 1);
+            p_word + (1.0 - p_word) * p_steps_left_at_most(remaining_entropy - e_info, left)
+        }
+    }
+}
+
+/// What a guess is ranked against: the answers it could still turn up, each with its
+/// (unnormalized) probability.
+///
+/// This is usually the remaining candidates of a `Solver`, but games that hide more than one
+/// answer keep track of theirs differently.
+trait Candidates: Sync {
+    /// How many candidates there are, which is how much work it is to rank a guess against them.
+    fn count(&self) -> usize;
+
+    /// Groups the candidates by the outcome they would produce if the word at `word_idx` were
+    /// guessed, and adds up `weigh(p)` of the candidates in each group in `totals`.
+    ///
+    /// Returns the probability that the word is itself an answer, unnormalized like the
+    /// candidates' ones, if it could be.
+    ///
+    /// `totals` is scratch space that must hold an element for every outcome.
+    fn fill_buckets(
+        &self,
+        word_idx: usize,
+        totals: &mut [f64],
+        weigh: impl Fn(f64) -> f64,
+    ) -> Option<f64>;
+}
 
-            let sum: f64 = totals
-                .into_iter()
-                .filter(|t| *t != 0.0)
-                .map(|p| {
-                    let p_of_this_pattern = p as f64 / remaining_p as f64;
-                    p_of_this_pattern * p_of_this_pattern.log2()
-                })
-                .sum();
-
-            let p_word = if in_remaining {
-                count as f64 / remaining_p as f64
+impl<F: Feedback> Candidates for Solver<F> {
+    fn count(&self) -> usize {
+        self.remaining.len()
+    }
+
+    /// Groups the remaining candidates by the outcome they would produce if the word at `word_idx`
+    /// were guessed, and adds up `weigh(p)` of the candidates in each group in `totals`.
+    ///
+    /// If outcomes may have lies in them, each candidate is instead added to every outcome that
+    /// could be shown for it, so the totals add up to `lie_spread` times as much.
+    ///
+    /// Returns the word's (unnormalized) probability if it is itself one of the remaining
+    /// candidates.
+    ///
+    /// `totals` is scratch space that must hold `F::outcomes(word_length)` elements.
+    fn fill_buckets(
+        &self,
+        word_idx: usize,
+        totals: &mut [f64],
+        weigh: impl Fn(f64) -> f64,
+    ) -> Option<f64> {
+        // considering a world where we _did_ guess `word` and got `pattern` as the
+        // correctness. now, compute what _then_ is left.
+
+        // Rather than iterate over the patterns sequentially and add up the counts of words
+        // that result in that pattern, we can instead keep a running total for each pattern
+        // simultaneously by storing them in an array. We can do this since each candidate-word
+        // pair deterministically produces only one mask.
+        totals.fill(0.0);
+
+        let mut in_remaining = None;
+        let dictionary = self.options.dictionary;
+        let encoded = dictionary.encoded(word_idx);
+        let (length, lies) = (dictionary.word_length(), self.options.lies);
+        let mut add = |outcome: u32, weight: f64| {
+            if lies == 0 {
+                totals[outcome as usize] += weight;
             } else {
-                // TODO: penalize further.
-                0.0
-            };
-            let e_info = -sum;
-            let goodness = match self.options.rank_by {
-                Rank::First => unreachable!("early return above"),
-                Rank::ExpectedScore => {
-                    // NOTE: Higher is better, so we negate the result.
-                    -(p_word * (score + 1.0)
-                        + (1.0 - p_word) * (score + est_steps_left(remaining_entropy - e_info)))
+                F::for_each_lie(outcome, length, lies, |shown| {
+                    totals[shown as usize] += weight
+                });
+            }
+        };
+        if let Some(cache) = &self.cache {
+            let row = cache.row(word_idx);
+            for (_, count, candidate_idx) in &*self.remaining {
+                if word_idx == *candidate_idx {
+                    in_remaining = Some(*count);
                 }
-                Rank::WeightedInformation => p_word * e_info,
-                Rank::InfoPlusProbability => p_word + e_info,
-                Rank::ExpectedInformation => e_info,
-            };
-            if let Some(c) = best {
-                // Which one gives us a lower (expected) score?
-                if goodness > c.goodness {
-                    best = Some(Candidate {
-                        word,
-                        goodness,
-                        idx: word_idx,
-                    });
+                add(row.get(*candidate_idx), weigh(*count));
+            }
+        } else {
+            for (_, count, candidate_idx) in &*self.remaining {
+                if word_idx == *candidate_idx {
+                    in_remaining = Some(*count);
                 }
-            } else {
-                best = Some(Candidate {
-                    word,
-                    goodness,
-                    idx: word_idx,
-                });
+                let candidate = dictionary.encoded(*candidate_idx);
+                let idx = F::compute(candidate, encoded);
+                add(idx, weigh(*count));
             }
+        }
+        in_remaining
+    }
+}
 
-            if self.options.cutoff && in_remaining {
-                i += 1;
-                if i >= stop {
-                    break;
-                }
+impl<F: Feedback> Solver<F> {
+    /// Picks the next word to guess, given the guesses made so far and their outcomes.
+    ///
+    /// Like with a `Guesser`, `history` must hold every earlier guess, in order, each time.
+    pub fn guess_scored(&mut self, history: &[Scored]) -> WordId {
+        let last = history.last().map(|g| (g.word.index(), g.outcome));
+        self.next_guess(history.len(), last)
+    }
+
+    /// Picks the guess for turn `turn + 1`, given the index and outcome of the previous guess.
+    fn next_guess(&mut self, turn: usize, last: Option<(usize, u32)>) -> WordId {
+        let score = turn as f64;
+
+        if let Some((last_guess_idx, outcome)) = last {
+            self.observe(last_guess_idx, outcome);
+        }
+
+        if turn == 0 {
+            if let Some(first_guess) = self.options.opener() {
+                // NOTE: I did a manual run with this commented out and it indeed produced "tares"
+                // as the first guess. It slows down the run by a lot though.
+                return self.options.dictionary.id(first_guess).unwrap();
             }
         }
-        let best = best.unwrap();
+        if self.remaining.len() == 1 {
+            return WordId::from_index(self.remaining[0].2);
+        }
+        if self.options.rank_by == Rank::First {
+            let first = self.remaining[0].2;
+            if self.guarantees() {
+                return WordId::from_index(self.guarantee(turn, score, first));
+            }
+            return WordId::from_index(first);
+        }
+        assert!(!self.remaining.is_empty());
+
+        let remaining = self.remaining_entropy();
+        self.entropy.push((turn, remaining.1));
+
+        let best = if self.looks_ahead() {
+            self.best_guess_ahead(score, remaining)
+        } else {
+            self.best_guess(score, remaining)
+        };
         assert_ne!(best.goodness, 0.0);
-        self.last_guess_idx = Some(best.idx);
-        best.word.to_string()
+        if self.guarantees() {
+            return WordId::from_index(self.guarantee(turn, score, best.idx));
+        }
+        WordId::from_index(best.idx)
     }
 
-    fn finish(&self, guesses: usize) {
+    /// The best word to guess according to `Options::rank_by`, after `score` guesses have been
+    /// made already.
+    fn best_guess(&self, score: f64, remaining: (f64, f64)) -> Candidate {
+        let mut best: Option<Candidate> = None;
+        self.rank_guesses(score, remaining, |c| {
+            // Which one gives us a lower (expected) score?
+            if best.is_none_or(|b| c.goodness > b.goodness) {
+                best = Some(c);
+            }
+        });
+        best.unwrap()
+    }
+
+    /// The `k` best words to guess according to `Options::rank_by`, best first.
+    fn best_guesses(&self, score: f64, remaining: (f64, f64), k: usize) -> Vec<Candidate> {
+        let mut best: Vec<Candidate> = Vec::with_capacity(k + 1);
+        self.rank_guesses(score, remaining, |c| {
+            // On ties, the earlier word stays ahead, like in `best_guess`.
+            let at = best.partition_point(|b| b.goodness >= c.goodness);
+            if at < k {
+                best.insert(at, c);
+                best.truncate(k);
+            }
+        });
+        best
+    }
+
+    /// Calls `rank` with how good each word that may be guessed next is, in dictionary order,
+    /// stopping early if `Options::cutoff` is set.
+    fn rank_guesses(&self, score: f64, remaining: (f64, f64), rank: impl FnMut(Candidate)) {
+        self.rank_words(self, self.considered(), score, remaining, rank)
+    }
+
+    /// Calls `rank` with how good each of `consider` would be to guess next against `candidates`,
+    /// in order.
+    ///
+    /// The words are spread over threads when there are enough of them.
+    fn rank_words(
+        &self,
+        candidates: &impl Candidates,
+        consider: &[(&'static str, f64, usize)],
+        score: f64,
+        (remaining_p, remaining_entropy): (f64, f64),
+        mut rank: impl FnMut(Candidate),
+    ) {
+        let outcomes = F::outcomes(self.options.dictionary.word_length());
+        let scratch = || (vec![0.0f64; outcomes], vec![0.0f64; outcomes]);
+        let evaluate = |(totals, plogp): &mut (Vec<f64>, Vec<f64>),
+                        &(_, _, word_idx): &(&str, f64, usize)| {
+            let (goodness, _) = self.evaluate_against(
+                candidates,
+                word_idx,
+                score,
+                (remaining_p, remaining_entropy),
+                totals,
+                plogp,
+            );
+            Candidate {
+                goodness,
+                idx: word_idx,
+            }
+        };
+
+        if consider.len() * candidates.count() < PARALLEL_WORK {
+            let mut scratch = scratch();
+            for word in consider {
+                rank(evaluate(&mut scratch, word));
+            }
+        } else {
+            // Every guess is evaluated on its own, and the candidates are handed to `rank` in the
+            // same order as above, so the result doesn't depend on how the work was split up.
+            let candidates: Vec<Candidate> =
+                consider.par_iter().map_init(scratch, evaluate).collect();
+            candidates.into_iter().for_each(rank);
+        }
+    }
+
+    /// The words `rank_guesses` evaluates: those in `consider`, up to the last one it takes with
+    /// `Options::cutoff`, which is the one that makes for `cutoff` of the remaining candidates.
+    fn considered(&self) -> &[(&'static str, f64, usize)] {
+        let consider = self.consider();
+        let stop = cutoff(self.remaining.len());
+        if !self.options.cutoff || stop == 0 {
+            return consider;
+        }
+        if self.options.hard_mode {
+            // Here, the words to consider are the remaining candidates.
+            return &consider[..stop];
+        }
+        // Otherwise every word is considered, in dictionary order, and the remaining candidates
+        // are in that order too.
+        let last = self.remaining[stop - 1].2;
+        &consider[..=last]
+    }
+
+    /// The entropy that was left before each guess the solver ranked in the game just played,
+    /// along with how many guesses it took to find the answer from there, counting that guess,
+    /// given that the game took `guesses` in all. This is what `StepsModel::fit` learns from.
+    pub fn steps_left(&self, guesses: usize) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.entropy
+            .iter()
+            .map(move |&(turn, entropy)| (entropy, guesses - turn))
+    }
+
+    /// Prints the estimation data of the game just played, if `PRINT_ESTIMATION` is set.
+    pub fn finish(&self, guesses: usize) {
         if PRINT_ESTIMATION {
-            for (i, &entropy) in self.entropy.iter().enumerate() {
-                // i == 0 is the entropy that was left _after_ guessing the first word.
-                // we want to print f(remaining entropy) -> number of guesses needed
-                // we know we ended up making `guesses` guesses, and we know this is the entropy after
-                // the (i+1)th guess, which means there are
-                let guesses_needed = guesses - (i + 1);
+            for (entropy, guesses_needed) in self.steps_left(guesses) {
                 println!("{} {}", entropy, guesses_needed);
             }
         }
     }
 }
 
+impl IdGuesser for Solver {
+    fn dictionary(&self) -> &'static Dictionary {
+        self.options.dictionary
+    }
+
+    fn guess_id(&mut self, history: &[IdGuess]) -> WordId {
+        let last = history
+            .last()
+            .map(|g| (g.word.index(), WordleFeedback::outcome(g.mask)));
+        self.next_guess(history.len(), last)
+    }
+
+    fn finish(&self, guesses: usize) {
+        Solver::finish(self, guesses)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 struct Candidate {
-    word: &'static str,
     goodness: f64,
     idx: usize,
 }
@@ -0,0 +1,527 @@
+use super::Options;
+use crate::{Dictionary, Feedback, Strategy, WordId, WordleFeedback};
+use std::collections::HashMap;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// The total of a position that can't be solved within the guess limit. Totals are added up with
+/// saturation, so anything unsolvable stays unsolvable.
+const UNSOLVABLE: u32 = u32::MAX;
+
+/// The first line of a checkpoint file, which also versions its format.
+const CHECKPOINT_MAGIC: &[u8] = b"roget exact checkpoint 1\n";
+
+/// The outcome of `ExactSolver::solve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    /// The total number of guesses `strategy` takes over every answer.
+    pub total: usize,
+
+    /// The number of answers.
+    pub games: usize,
+
+    /// A total that no strategy can beat. If this is `total`, `strategy` is optimal.
+    pub lower_bound: usize,
+
+    pub strategy: Strategy,
+}
+
+impl Solution {
+    /// The average number of guesses `strategy` takes per answer.
+    pub fn average(&self) -> f64 {
+        self.total as f64 / self.games as f64
+    }
+
+    /// True if no strategy takes fewer guesses in total.
+    pub fn is_optimal(&self) -> bool {
+        self.total == self.lower_bound
+    }
+}
+
+/// What is known about the best total of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Known {
+    /// The best total, and the guess that achieves it.
+    Exact { total: u32, guess: u32 },
+
+    /// No total below this is possible.
+    AtLeast(u32),
+}
+
+/// A position: how many guesses are left, the answers that are still possible (as positions into
+/// the answer list), and in hard mode, the words that may still be guessed.
+///
+/// Every guess worth making rules out at least one of the possible answers, so the guess limit
+/// makes no difference once there are as many guesses left as possible answers; such positions
+/// share a key.
+type Key = (u32, Box<[u32]>, Option<Box<[u32]>>);
+
+/// A solver that finds the strategy that takes the fewest guesses in total over every answer, by
+/// branch-and-bound search over the tree of guesses and feedback.
+///
+/// Every position is memoised by the answers that are still possible, so each is only ever
+/// solved once. Guesses are tried in order of a lower bound on what they could achieve, and
+/// positions are given up on as soon as they can't beat the best guess found so far.
+///
+/// In hard mode, only words that could still be the answer may be guessed, like with
+/// `Options::hard_mode`. Solving a full dictionary can take hours, so progress can be saved to a
+/// checkpoint and picked up again later; see `with_checkpoint`.
+pub struct ExactSolver {
+    dictionary: &'static Dictionary,
+    hard_mode: bool,
+    max_guesses: usize,
+    shortlist: Option<usize>,
+
+    /// The index of each answer in the dictionary.
+    answers: Vec<u32>,
+    /// The outcome of each word as a guess against each answer, at `guess * answers.len() +
+    /// answer`.
+    outcomes: Vec<u32>,
+    memo: HashMap<Key, Known>,
+
+    checkpoint: Option<(PathBuf, Duration)>,
+    last_checkpoint: Instant,
+}
+
+impl Options {
+    /// Builds a solver that finds the optimal strategy for the dictionary and hard mode setting
+    /// of these options.
+    pub fn build_exact(self) -> ExactSolver {
+        let dictionary = self.dictionary;
+        let answers: Vec<u32> = dictionary
+            .answers()
+            .iter()
+            .map(|answer| {
+                let id = dictionary
+                    .id(answer)
+                    .expect("answers are in the dictionary");
+                id.index() as u32
+            })
+            .collect();
+        let mut outcomes = Vec::with_capacity(dictionary.len() * answers.len());
+        for guess in 0..dictionary.len() {
+            let guess = dictionary.encoded(guess);
+            outcomes.extend(answers.iter().map(|&answer| {
+                WordleFeedback::compute(dictionary.encoded(answer as usize), guess)
+            }));
+        }
+        ExactSolver {
+            dictionary,
+            hard_mode: self.hard_mode,
+            max_guesses: usize::MAX,
+            shortlist: None,
+            answers,
+            outcomes,
+            memo: HashMap::new(),
+            checkpoint: None,
+            last_checkpoint: Instant::now(),
+        }
+    }
+}
+
+impl ExactSolver {
+    /// Only accepts strategies that find every answer within `max_guesses` guesses.
+    pub fn with_max_guesses(mut self, max_guesses: usize) -> Self {
+        self.max_guesses = max_guesses;
+        self
+    }
+
+    /// Only tries the `n` most promising guesses in each position, which is much faster, but
+    /// may miss the optimal strategy. `Solution::lower_bound` then says how far off it can be.
+    pub fn with_shortlist(mut self, n: usize) -> Self {
+        self.shortlist = Some(n);
+        self
+    }
+
+    /// Saves what has been solved so far to `path` every `every`, and when solving is done.
+    ///
+    /// If `path` already holds a checkpoint, it is loaded, so that solving picks up where it
+    /// left off. Fails if the checkpoint was made with different settings.
+    pub fn with_checkpoint(
+        mut self,
+        path: impl Into<PathBuf>,
+        every: Duration,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        match std::fs::File::open(&path) {
+            Ok(f) => self.load(BufReader::new(f))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.checkpoint = Some((path, every));
+        self.last_checkpoint = Instant::now();
+        Ok(self)
+    }
+
+    /// The number of positions solved, or bounded, so far.
+    pub fn positions(&self) -> usize {
+        self.memo.len()
+    }
+
+    /// Finds the strategy that takes the fewest guesses in total over every answer.
+    ///
+    /// Returns `None` if there is no strategy that finds every answer within the guess limit.
+    pub fn solve(&mut self) -> io::Result<Option<Solution>> {
+        let candidates: Vec<u32> = (0..self.answers.len() as u32).collect();
+        let allowed: Option<Vec<u32>> = self
+            .hard_mode
+            .then(|| (0..self.dictionary.len() as u32).collect());
+        let known = self.search(&candidates, allowed.as_deref(), 0, UNSOLVABLE)?;
+        self.save()?;
+        let Known::Exact { total, .. } = known else {
+            return Ok(None);
+        };
+        if total == UNSOLVABLE {
+            return Ok(None);
+        }
+
+        // With a shortlist, the guesses that weren't tried might have done better, so all we
+        // know for sure is that no guess beats its lower bound.
+        let lower_bound = match self.shortlist {
+            None => total,
+            Some(_) => self
+                .ranked(&candidates, allowed.as_deref())
+                .first()
+                .map_or(total, |&(bound, _)| bound.min(total)),
+        };
+
+        let strategy = self.strategy(&candidates, allowed.as_deref(), 0);
+        Ok(Some(Solution {
+            total: total as usize,
+            games: self.answers.len(),
+            lower_bound: lower_bound as usize,
+            strategy,
+        }))
+    }
+
+    /// A lower bound on the total of any position with `n` possible answers.
+    ///
+    /// At best, the next guess is one of the answers, and each other answer gets feedback of its
+    /// own and is guessed right after. There are only so many kinds of feedback though, so the
+    /// answers that have to share one take at least a third guess.
+    fn lower_bound(&self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        let outcomes = WordleFeedback::outcomes(self.dictionary.word_length()) as u32;
+        2 * n - 1 + n.saturating_sub(outcomes)
+    }
+
+    /// A lower bound on the total of a position with `candidates` if `guess` is guessed next,
+    /// or `None` if `guess` wouldn't tell any of the candidates apart.
+    ///
+    /// `counts` is scratch space.
+    fn bound_for(
+        &self,
+        candidates: &[u32],
+        guess: u32,
+        counts: &mut HashMap<u32, u32>,
+    ) -> Option<u32> {
+        counts.clear();
+        let row = &self.outcomes[guess as usize * self.answers.len()..][..self.answers.len()];
+        for &c in candidates {
+            *counts.entry(row[c as usize]).or_insert(0) += 1;
+        }
+        let solved = counts.contains_key(&0);
+        if counts.len() == 1 && !solved {
+            return None;
+        }
+        let n = candidates.len() as u32;
+        Some(
+            n + counts
+                .iter()
+                .filter(|&(&outcome, _)| outcome != 0)
+                .map(|(_, &count)| self.lower_bound(count))
+                .sum::<u32>(),
+        )
+    }
+
+    /// The guesses that are worth trying with `candidates` left, along with a lower bound on the
+    /// total each could achieve, most promising first.
+    fn ranked(&self, candidates: &[u32], allowed: Option<&[u32]>) -> Vec<(u32, u32)> {
+        let mut counts = HashMap::new();
+        let mut ranked: Vec<(u32, u32)> = match allowed {
+            Some(allowed) => allowed
+                .iter()
+                .filter_map(|&guess| Some((self.bound_for(candidates, guess, &mut counts)?, guess)))
+                .collect(),
+            None => (0..self.dictionary.len() as u32)
+                .filter_map(|guess| Some((self.bound_for(candidates, guess, &mut counts)?, guess)))
+                .collect(),
+        };
+        // Ties go to the more common word, which the dictionary puts first.
+        ranked.sort_unstable();
+        ranked
+    }
+
+    /// Splits `candidates` by the feedback `guess` gets for each, leaving out the answer
+    /// `guess` itself. The biggest groups come first.
+    fn partition(&self, candidates: &[u32], guess: u32) -> Vec<(u32, Vec<u32>)> {
+        let row = &self.outcomes[guess as usize * self.answers.len()..][..self.answers.len()];
+        let mut groups: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &c in candidates {
+            let outcome = row[c as usize];
+            if outcome != 0 {
+                groups.entry(outcome).or_default().push(c);
+            }
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+        groups
+    }
+
+    /// The words of `allowed` that could still be the answer after `guess` got `outcome`.
+    fn narrow(&self, allowed: Option<&[u32]>, guess: u32, outcome: u32) -> Option<Vec<u32>> {
+        let guess = self.dictionary.encoded(guess as usize);
+        allowed.map(|allowed| {
+            allowed
+                .iter()
+                .copied()
+                .filter(|&w| {
+                    WordleFeedback::compute(self.dictionary.encoded(w as usize), guess) == outcome
+                })
+                .collect()
+        })
+    }
+
+    /// The best total of the position with `candidates` left and `turn` guesses made, if it is
+    /// below `beta`. If it isn't, returns a lower bound that is at least `beta` instead.
+    fn search(
+        &mut self,
+        candidates: &[u32],
+        allowed: Option<&[u32]>,
+        turn: usize,
+        beta: u32,
+    ) -> io::Result<Known> {
+        let n = candidates.len() as u32;
+        let left = self.max_guesses.saturating_sub(turn);
+        if left == 0 || (left == 1 && n > 1) {
+            return Ok(Known::Exact {
+                total: UNSOLVABLE,
+                guess: 0,
+            });
+        }
+        if n == 1 {
+            return Ok(Known::Exact {
+                total: 1,
+                guess: self.answers[candidates[0] as usize],
+            });
+        }
+        let key: Key = (
+            left.min(n as usize) as u32,
+            candidates.into(),
+            allowed.map(Into::into),
+        );
+        match self.memo.get(&key) {
+            Some(&known @ Known::Exact { .. }) => return Ok(known),
+            Some(&Known::AtLeast(bound)) if bound >= beta => return Ok(Known::AtLeast(bound)),
+            _ => {}
+        }
+        let floor = self.lower_bound(n);
+        if floor >= beta {
+            return Ok(Known::AtLeast(floor));
+        }
+
+        let mut ranked = self.ranked(candidates, allowed);
+        if let Some(n) = self.shortlist {
+            ranked.truncate(n);
+        }
+        let mut best = beta;
+        let mut best_guess = None;
+        'guesses: for (bound, guess) in ranked {
+            // The rest of the guesses can only be worse.
+            if bound >= best {
+                break;
+            }
+            let groups = self.partition(candidates, guess);
+            let mut total = n;
+            let mut rest = bound - n;
+            for (outcome, group) in groups {
+                rest -= self.lower_bound(group.len() as u32);
+                let group_beta = best.saturating_sub(total.saturating_add(rest));
+                let allowed = self.narrow(allowed, guess, outcome);
+                match self.search(&group, allowed.as_deref(), turn + 1, group_beta)? {
+                    Known::Exact { total: t, .. } if t < group_beta => total += t,
+                    _ => continue 'guesses,
+                }
+            }
+            best = total;
+            best_guess = Some(guess);
+        }
+
+        let known = match best_guess {
+            Some(guess) => Known::Exact { total: best, guess },
+            None => Known::AtLeast(beta),
+        };
+        let entry = self.memo.entry(key).or_insert(known);
+        if let (Known::AtLeast(old), Known::AtLeast(new)) = (*entry, known) {
+            *entry = Known::AtLeast(old.max(new));
+        } else if let Known::Exact { .. } = known {
+            *entry = known;
+        }
+
+        if let Some((_, every)) = &self.checkpoint {
+            if self.last_checkpoint.elapsed() >= *every {
+                self.save()?;
+            }
+        }
+        Ok(known)
+    }
+
+    /// The strategy for the solved position with `candidates` left and `turn` guesses made.
+    fn strategy(&self, candidates: &[u32], allowed: Option<&[u32]>, turn: usize) -> Strategy {
+        let guess = if let [answer] = candidates {
+            self.answers[*answer as usize]
+        } else {
+            let left = self.max_guesses.saturating_sub(turn).min(candidates.len());
+            let key: Key = (left as u32, candidates.into(), allowed.map(Into::into));
+            match self.memo.get(&key) {
+                Some(Known::Exact { guess, .. }) => *guess,
+                _ => unreachable!("the positions of a solved strategy are all solved"),
+            }
+        };
+        let length = self.dictionary.word_length();
+        let mut next: Vec<_> = self
+            .partition(candidates, guess)
+            .into_iter()
+            .map(|(outcome, group)| {
+                let allowed = self.narrow(allowed, guess, outcome);
+                let mask = WordleFeedback::mask(outcome, length);
+                (mask, self.strategy(&group, allowed.as_deref(), turn + 1))
+            })
+            .collect();
+        next.sort_unstable_by_key(|&(mask, _)| mask);
+        Strategy {
+            guess: WordId::from_index(guess as usize),
+            solves: candidates
+                .iter()
+                .any(|&c| self.answers[c as usize] == guess),
+            next,
+        }
+    }
+
+    /// Everything a checkpoint has to agree on to be loaded.
+    fn settings(&self) -> [u64; 4] {
+        [
+            self.dictionary.fingerprint(),
+            u64::from(self.hard_mode),
+            self.max_guesses as u64,
+            self.shortlist.map_or(u64::MAX, |n| n as u64),
+        ]
+    }
+
+    /// Writes every position solved so far to the checkpoint file, if there is one.
+    ///
+    /// The file is written next to the checkpoint and then moved over it, so that a checkpoint
+    /// is never left half-written.
+    fn save(&mut self) -> io::Result<()> {
+        let Some((path, _)) = &self.checkpoint else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("tmp");
+        let mut w = BufWriter::new(std::fs::File::create(&tmp)?);
+        w.write_all(CHECKPOINT_MAGIC)?;
+        for setting in self.settings() {
+            w.write_all(&setting.to_le_bytes())?;
+        }
+        write_u32(&mut w, self.memo.len() as u32)?;
+        for ((left, candidates, allowed), known) in &self.memo {
+            write_u32(&mut w, *left)?;
+            write_u32s(&mut w, candidates)?;
+            match allowed {
+                Some(allowed) => {
+                    w.write_all(&[1])?;
+                    write_u32s(&mut w, allowed)?;
+                }
+                None => w.write_all(&[0])?,
+            }
+            match *known {
+                Known::Exact { total, guess } => {
+                    w.write_all(&[0])?;
+                    write_u32(&mut w, total)?;
+                    write_u32(&mut w, guess)?;
+                }
+                Known::AtLeast(bound) => {
+                    w.write_all(&[1])?;
+                    write_u32(&mut w, bound)?;
+                }
+            }
+        }
+        w.into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        self.last_checkpoint = Instant::now();
+        Ok(())
+    }
+
+    /// Adds every position of a checkpoint to the memo.
+    fn load(&mut self, mut r: impl Read) -> io::Result<()> {
+        let mut magic = [0; CHECKPOINT_MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        if magic != CHECKPOINT_MAGIC {
+            return Err(invalid(
+                "not a checkpoint, or one of an unsupported version",
+            ));
+        }
+        for setting in self.settings() {
+            let mut buf = [0; 8];
+            r.read_exact(&mut buf)?;
+            if u64::from_le_bytes(buf) != setting {
+                return Err(invalid(
+                    "the checkpoint was made with a different dictionary or settings",
+                ));
+            }
+        }
+        for _ in 0..read_u32(&mut r)? {
+            let left = read_u32(&mut r)?;
+            let candidates = read_u32s(&mut r)?;
+            let allowed = match read_u8(&mut r)? {
+                0 => None,
+                1 => Some(read_u32s(&mut r)?),
+                _ => return Err(invalid("corrupt checkpoint")),
+            };
+            let known = match read_u8(&mut r)? {
+                0 => Known::Exact {
+                    total: read_u32(&mut r)?,
+                    guess: read_u32(&mut r)?,
+                },
+                1 => Known::AtLeast(read_u32(&mut r)?),
+                _ => return Err(invalid("corrupt checkpoint")),
+            };
+            self.memo.insert((left, candidates, allowed), known);
+        }
+        Ok(())
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_u32(w: &mut impl Write, n: u32) -> io::Result<()> {
+    w.write_all(&n.to_le_bytes())
+}
+
+fn write_u32s(w: &mut impl Write, ns: &[u32]) -> io::Result<()> {
+    write_u32(w, ns.len() as u32)?;
+    ns.iter().try_for_each(|&n| write_u32(w, n))
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u32s(r: &mut impl Read) -> io::Result<Box<[u32]>> {
+    let len = read_u32(r)?;
+    (0..len).map(|_| read_u32(r)).collect()
+}
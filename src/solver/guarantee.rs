@@ -0,0 +1,140 @@
+use super::{minimax, Rank, Solver};
+use crate::Feedback;
+use std::collections::HashMap;
+
+/// How many guesses are tried, smallest worst case first, in showing that a position can be
+/// finished in time. When none of these do, the rest practically never do either, and trying
+/// them all would take far too long.
+const PROOF_WIDTH: usize = 50;
+
+/// Positions already shown to be finishable in time, or not, keyed by their candidates and the
+/// guesses left.
+type Proofs = HashMap<(Vec<usize>, usize), bool>;
+
+impl<F: Feedback> Solver<F> {
+    /// True if guesses have to pass `Options::guarantee`.
+    pub(super) fn guarantees(&self) -> bool {
+        // With lies, no outcome rules out a candidate for sure, so nothing can be shown.
+        self.options.guarantee && self.options.lies == 0
+    }
+
+    /// The word to guess at `turn` instead of the one at `best_idx` if that one can't be shown to
+    /// find every remaining candidate within `Options::max_guesses`.
+    ///
+    /// The other guesses are tried best first by `Options::rank_by` (unless that is `Rank::First`),
+    /// and if none of those can be shown to either, by their worst case. If no guess can be shown
+    /// to make it, the game can already be lost, and `best_idx` is returned.
+    pub(super) fn guarantee(&self, turn: usize, score: f64, best_idx: usize) -> usize {
+        let left = self.options.max_guesses.saturating_sub(turn);
+        let candidates: Vec<usize> = self.remaining.iter().map(|&(_, _, idx)| idx).collect();
+        let mut proofs = Proofs::new();
+        if left == 0 || self.finishes(&candidates, best_idx, left, &mut proofs) {
+            return best_idx;
+        }
+
+        let mut ranked = Vec::new();
+        if self.options.rank_by != Rank::First {
+            self.rank_guesses(score, self.remaining_entropy(), |c| ranked.push(c));
+            ranked.sort_by(|a, b| b.goodness.total_cmp(&a.goodness));
+        }
+        ranked
+            .iter()
+            .take(PROOF_WIDTH)
+            .map(|c| c.idx)
+            .find(|&idx| idx != best_idx && self.finishes(&candidates, idx, left, &mut proofs))
+            .or_else(|| self.finisher(&candidates, left, &mut proofs))
+            .unwrap_or(best_idx)
+    }
+
+    /// True if, with `left` guesses left, guessing the word at `guess_idx` can be shown to find
+    /// every one of `candidates` in time.
+    fn finishes(
+        &self,
+        candidates: &[usize],
+        guess_idx: usize,
+        left: usize,
+        proofs: &mut Proofs,
+    ) -> bool {
+        let length = self.options.dictionary.word_length();
+        let mut buckets: HashMap<u32, Vec<usize>> = HashMap::new();
+        for &idx in candidates {
+            let outcome = self.outcome(guess_idx, idx);
+            if !F::is_solved(outcome, length) {
+                buckets.entry(outcome).or_default().push(idx);
+            }
+        }
+        buckets
+            .values()
+            .all(|bucket| self.finishable(bucket, left - 1, proofs))
+    }
+
+    /// True if every one of `candidates` can be shown to be found within `left` guesses.
+    fn finishable(&self, candidates: &[usize], left: usize, proofs: &mut Proofs) -> bool {
+        let n = candidates.len();
+        // Guessing the candidates one by one rules out at least one of them every time.
+        if n <= left {
+            return true;
+        }
+        if left <= 1 {
+            return false;
+        }
+        let key = (candidates.to_vec(), left);
+        if let Some(&known) = proofs.get(&key) {
+            return known;
+        }
+        let finishable = self.finisher(candidates, left, proofs).is_some();
+        proofs.insert(key, finishable);
+        finishable
+    }
+
+    /// A guess that can be shown to find every one of `candidates` within `left` guesses, if
+    /// there is one among the `PROOF_WIDTH` with the smallest worst case.
+    fn finisher(&self, candidates: &[usize], left: usize, proofs: &mut Proofs) -> Option<usize> {
+        let dictionary = self.options.dictionary;
+        let mut counts = vec![0usize; F::outcomes(dictionary.word_length())];
+        let mut worst = |guess_idx: usize| {
+            counts.fill(0);
+            for &idx in candidates {
+                counts[self.outcome(guess_idx, idx) as usize] += 1;
+            }
+            *counts.iter().max().unwrap()
+        };
+        // In hard mode, only the candidates themselves may be guessed.
+        let mut ranked: Vec<(usize, bool, usize)> = if self.options.hard_mode {
+            candidates
+                .iter()
+                .map(|&idx| (worst(idx), true, idx))
+                .collect()
+        } else {
+            (0..dictionary.len())
+                .map(|idx| (worst(idx), candidates.contains(&idx), idx))
+                .collect()
+        };
+        ranked.sort_unstable_by(|&(a, a_wins, a_idx), &(b, b_wins, b_idx)| {
+            minimax(b, b_wins)
+                .total_cmp(&minimax(a, a_wins))
+                .then(a_idx.cmp(&b_idx))
+        });
+        ranked
+            .into_iter()
+            // A guess that doesn't tell any of the candidates apart gets us nowhere.
+            .take_while(|&(worst, _, _)| worst < candidates.len())
+            .take(PROOF_WIDTH)
+            // With two guesses left, the second one has to be the answer.
+            .filter(|&(worst, _, _)| left > 2 || worst <= 1)
+            .map(|(_, _, idx)| idx)
+            .find(|&idx| self.finishes(candidates, idx, left, proofs))
+    }
+
+    /// The outcome of guessing the word at `guess_idx` if the word at `answer_idx` is the answer.
+    fn outcome(&self, guess_idx: usize, answer_idx: usize) -> u32 {
+        let dictionary = self.options.dictionary;
+        match &self.cache {
+            Some(cache) => cache.row(guess_idx).get(answer_idx),
+            None => F::compute(
+                dictionary.encoded(answer_idx),
+                dictionary.encoded(guess_idx),
+            ),
+        }
+    }
+}
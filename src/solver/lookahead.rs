@@ -0,0 +1,119 @@
+use super::{Candidate, Candidates, Rank, Solver};
+use crate::Feedback;
+use serde::{Deserialize, Serialize};
+
+/// How far ahead the solver looks before picking a guess; see `Options::lookahead`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Lookahead {
+    /// How many guesses to look ahead, counting the one being picked. At 1, guesses are ranked
+    /// by their own expected score alone.
+    pub depth: usize,
+
+    /// How many of the best guesses by expected score are looked further ahead at each step.
+    pub width: usize,
+
+    /// How many positions may be looked at in picking a single guess. Once it runs out, the
+    /// remaining positions are judged by their expected score alone, which keeps the time per
+    /// guess bounded.
+    pub budget: usize,
+}
+
+impl Default for Lookahead {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            width: 5,
+            budget: 2000,
+        }
+    }
+}
+
+impl<F: Feedback> Solver<F> {
+    /// True if guesses should be picked by looking further ahead than the next one.
+    pub(super) fn looks_ahead(&self) -> bool {
+        // With lies, outcomes can't tell when the answer was guessed, which the search relies on.
+        self.options.rank_by == Rank::ExpectedScore
+            && self.options.lookahead.depth > 1
+            && self.options.lies == 0
+    }
+
+    /// The best word to guess by its expected score when looking `Options::lookahead` guesses
+    /// ahead, after `score` guesses have been made already.
+    pub(super) fn best_guess_ahead(&self, score: f64, remaining: (f64, f64)) -> Candidate {
+        let mut budget = self.options.lookahead.budget;
+        let (expected, idx) =
+            self.search(score, remaining, self.options.lookahead.depth, &mut budget);
+        // Like with `Rank::ExpectedScore`, higher is better.
+        Candidate {
+            goodness: -expected,
+            idx,
+        }
+    }
+
+    /// The expected score of the game, and the guess that achieves it, when looking `depth`
+    /// guesses ahead.
+    fn search(
+        &self,
+        score: f64,
+        remaining: (f64, f64),
+        depth: usize,
+        budget: &mut usize,
+    ) -> (f64, usize) {
+        if self.remaining.len() == 1 {
+            return (score + 1.0, self.remaining[0].2);
+        }
+        if depth <= 1 || *budget == 0 {
+            let best = self.best_guess(score, remaining);
+            // `Rank::ExpectedScore` doesn't count the guess itself when it misses, which doesn't
+            // matter when comparing guesses with each other, but does when comparing them with
+            // the exact scores of positions we did look ahead from.
+            let p_word = self
+                .remaining
+                .iter()
+                .find(|&&(_, _, idx)| idx == best.idx)
+                .map_or(0.0, |&(_, p, _)| p / remaining.0);
+            return (-best.goodness + (1.0 - p_word), best.idx);
+        }
+
+        let mut best: Option<(f64, usize)> = None;
+        for c in self.best_guesses(score, remaining, self.options.lookahead.width) {
+            let expected = self.expected_score(c.idx, score, remaining.0, depth, budget);
+            if best.is_none_or(|(b, _)| expected < b) {
+                best = Some((expected, c.idx));
+            }
+        }
+        best.unwrap()
+    }
+
+    /// The expected score of the game if the word at `word_idx` is guessed next, and the best
+    /// follow-up is picked for every outcome by looking `depth - 1` guesses further ahead.
+    fn expected_score(
+        &self,
+        word_idx: usize,
+        score: f64,
+        remaining_p: f64,
+        depth: usize,
+        budget: &mut usize,
+    ) -> f64 {
+        let length = self.options.dictionary.word_length();
+        let mut totals = vec![0.0f64; F::outcomes(length)];
+        self.fill_buckets(word_idx, &mut totals, |p| p);
+
+        let mut expected = 0.0;
+        for (outcome, &total) in totals.iter().enumerate().filter(|&(_, &t)| t != 0.0) {
+            let outcome = outcome as u32;
+            let p = total / remaining_p;
+            if F::is_solved(outcome, length) {
+                expected += p * (score + 1.0);
+                continue;
+            }
+            *budget = budget.saturating_sub(1);
+            let mut next = self.clone();
+            next.observe(word_idx, outcome);
+            let remaining = next.remaining_entropy();
+            expected += p * next.search(score + 1.0, remaining, depth - 1, budget).0;
+        }
+        expected
+    }
+}
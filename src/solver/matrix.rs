@@ -0,0 +1,245 @@
+use super::Options;
+use crate::{Dictionary, Feedback};
+use memmap2::Mmap;
+use once_cell::sync::Lazy;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The start of every matrix file. This changes whenever the format does.
+const MATRIX_MAGIC: &[u8] = b"roget patterns 1\n";
+
+/// The matrix for each `Dictionary` and `Feedback`, keyed by the dictionary's address and the
+/// feedback's type, and shared by every solver on every thread.
+static MATRICES: Lazy<Mutex<HashMap<(usize, TypeId), Arc<Matrix>>>> = Lazy::new(Default::default);
+
+/// The `Feedback` outcome of every word of a `Dictionary` guessed against every other.
+///
+/// The outcome of guess `g` against answer `a` is entry `g * dictionary.len() + a`. Every entry is
+/// as few little-endian bytes as all outcomes fit in, which is a single one for Wordle with up to
+/// five letters.
+pub(super) struct Matrix {
+    len: usize,
+    width: usize,
+    data: Data,
+}
+
+enum Data {
+    Built(Vec<u8>),
+    Mapped(Mmap),
+}
+
+/// The outcomes of guessing one word against every word.
+#[derive(Clone, Copy)]
+pub(super) struct Row<'a> {
+    bytes: &'a [u8],
+    width: usize,
+}
+
+impl Row<'_> {
+    /// The outcome if the word at `answer_idx` is the answer.
+    #[inline]
+    pub(super) fn get(self, answer_idx: usize) -> u32 {
+        match self.width {
+            1 => u32::from(self.bytes[answer_idx]),
+            2 => u32::from(u16::from_le_bytes([
+                self.bytes[2 * answer_idx],
+                self.bytes[2 * answer_idx + 1],
+            ])),
+            _ => u32::from_le_bytes(self.bytes[4 * answer_idx..][..4].try_into().unwrap()),
+        }
+    }
+}
+
+impl Matrix {
+    /// The matrix for `dictionary` and `F`, which is built the first time it is asked for, unless
+    /// `Options::load_pattern_matrix` loaded it already.
+    pub(super) fn shared<F: Feedback>(dictionary: &'static Dictionary) -> Arc<Self> {
+        // We hold on to the lock while building, so the same matrix is never built twice.
+        let mut matrices = MATRICES.lock().unwrap();
+        Arc::clone(
+            matrices
+                .entry(key::<F>(dictionary))
+                .or_insert_with(|| Arc::new(Self::build::<F>(dictionary))),
+        )
+    }
+
+    /// Computes every outcome, spread over as many threads as the current thread pool has.
+    fn build<F: Feedback>(dictionary: &Dictionary) -> Self {
+        let (len, width) = (dictionary.len(), width::<F>(dictionary));
+        let mut data = vec![0u8; len * len * width];
+        // We start threads of our own rather than use the pool's, since a pool thread that waits
+        // for the rest of the work might pick up a game that needs this very matrix in the
+        // meantime, and wait for the lock that is held while building it forever.
+        let rows = len.div_ceil(rayon::current_num_threads()).max(1);
+        std::thread::scope(|s| {
+            for (chunk_idx, chunk) in data.chunks_mut((rows * len * width).max(1)).enumerate() {
+                s.spawn(move || {
+                    for (i, row) in chunk.chunks_exact_mut(len * width).enumerate() {
+                        let guess = dictionary.encoded(chunk_idx * rows + i);
+                        for (answer_idx, entry) in row.chunks_exact_mut(width).enumerate() {
+                            let outcome = F::compute(dictionary.encoded(answer_idx), guess);
+                            entry.copy_from_slice(&outcome.to_le_bytes()[..width]);
+                        }
+                    }
+                });
+            }
+        });
+        Self {
+            len,
+            width,
+            data: Data::Built(data),
+        }
+    }
+
+    /// The outcomes of guessing the word at `guess_idx`.
+    #[inline]
+    pub(super) fn row(&self, guess_idx: usize) -> Row<'_> {
+        let stride = self.len * self.width;
+        Row {
+            bytes: &self.bytes()[guess_idx * stride..][..stride],
+            width: self.width,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.data {
+            Data::Built(data) => data,
+            Data::Mapped(map) => &map[header_len()..],
+        }
+    }
+
+    /// The fields of the header of the file for this matrix, after `MATRIX_MAGIC`, but for the
+    /// checksum of the outcomes that ends it.
+    fn header<F: Feedback>(&self, dictionary: &Dictionary) -> [u64; 4] {
+        [
+            dictionary.fingerprint(),
+            crate::fnv1a(&[std::any::type_name::<F>().as_bytes()]),
+            self.len as u64,
+            self.width as u64,
+        ]
+    }
+
+    /// Maps the matrix file at `path`, and checks that it holds the matrix for `dictionary` and
+    /// `F`.
+    ///
+    /// Only the header and the file's length are checked, so that loading doesn't read the whole
+    /// file, unless `verify` is set, in which case the outcomes are checked against the checksum
+    /// that was written along with them.
+    fn read<F: Feedback>(dictionary: &Dictionary, path: &Path, verify: bool) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: Matrix files are only ever replaced by renaming a new file over them, never
+        // written to in place, so what is mapped here doesn't change under us.
+        let map = unsafe { Mmap::map(&file)? };
+        let (len, width) = (dictionary.len(), width::<F>(dictionary));
+        if map.len() != header_len() + len * len * width || !map.starts_with(MATRIX_MAGIC) {
+            return Err(invalid(
+                "not a pattern matrix of the right size, or one of an unsupported version",
+            ));
+        }
+        let header: Vec<u64> = map[MATRIX_MAGIC.len()..header_len()]
+            .chunks_exact(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let matrix = Self {
+            len,
+            width,
+            data: Data::Mapped(map),
+        };
+        if header[..4] != matrix.header::<F>(dictionary) {
+            return Err(invalid(
+                "the pattern matrix was made for a different dictionary",
+            ));
+        }
+        if verify && header[4] != crate::fnv1a(&[matrix.bytes()]) {
+            return Err(invalid("the pattern matrix is corrupt"));
+        }
+        Ok(matrix)
+    }
+
+    /// Writes the matrix for `dictionary` and `F` to `path`.
+    fn write<F: Feedback>(&self, dictionary: &Dictionary, path: &Path) -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut f = std::fs::File::create(&tmp)?;
+        f.write_all(MATRIX_MAGIC)?;
+        for field in self.header::<F>(dictionary) {
+            f.write_all(&field.to_le_bytes())?;
+        }
+        f.write_all(&crate::fnv1a(&[self.bytes()]).to_le_bytes())?;
+        f.write_all(self.bytes())?;
+        f.sync_all()?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+impl<F: Feedback> Options<F> {
+    /// Has solvers with `cache` set look outcomes up in a matrix that is kept in `dir`, so that it
+    /// only has to be computed once for each dictionary.
+    ///
+    /// The matrix is memory-mapped from its file if an earlier call left one there. A file that
+    /// can't be read, or that was made for a different dictionary, is computed again and
+    /// overwritten. If the new file can't be written, the matrix is still used, and the error is
+    /// returned.
+    ///
+    /// Only the file's header is checked, so a file whose outcomes were damaged after it was
+    /// written is used as it is; see `verify_pattern_matrix`.
+    pub fn load_pattern_matrix(&self, dir: &Path) -> io::Result<()> {
+        self.load_matrix(dir, false)
+    }
+
+    /// Like `load_pattern_matrix`, but also checks every outcome in the file against the
+    /// checksum written along with them, and computes them again if they don't match.
+    ///
+    /// This reads the whole file, which `load_pattern_matrix` avoids.
+    pub fn verify_pattern_matrix(&self, dir: &Path) -> io::Result<()> {
+        self.load_matrix(dir, true)
+    }
+
+    fn load_matrix(&self, dir: &Path, verify: bool) -> io::Result<()> {
+        let dictionary = self.dictionary;
+        let path = dir.join(format!(
+            "patterns-{:016x}.bin",
+            crate::fnv1a(&[
+                &dictionary.fingerprint().to_le_bytes(),
+                std::any::type_name::<F>().as_bytes()
+            ])
+        ));
+        let (matrix, read) = match Matrix::read::<F>(dictionary, &path, verify) {
+            Ok(matrix) => (matrix, true),
+            Err(_) => (Matrix::build::<F>(dictionary), false),
+        };
+        let matrix = Arc::new(matrix);
+        MATRICES
+            .lock()
+            .unwrap()
+            .insert(key::<F>(dictionary), Arc::clone(&matrix));
+        if read {
+            return Ok(());
+        }
+        std::fs::create_dir_all(dir)?;
+        matrix.write::<F>(dictionary, &path)
+    }
+}
+
+fn key<F: Feedback>(dictionary: &Dictionary) -> (usize, TypeId) {
+    (dictionary as *const Dictionary as usize, TypeId::of::<F>())
+}
+
+/// The number of bytes it takes to hold any outcome of `F` for the words of `dictionary`.
+fn width<F: Feedback>(dictionary: &Dictionary) -> usize {
+    match F::outcomes(dictionary.word_length()) {
+        0..=0x100 => 1,
+        0x101..=0x1_0000 => 2,
+        _ => 4,
+    }
+}
+
+fn header_len() -> usize {
+    MATRIX_MAGIC.len() + 5 * 8
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
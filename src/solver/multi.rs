@@ -0,0 +1,129 @@
+use super::{cutoff, Candidate, Options, Rank, Solver};
+use crate::{Guess, MultiGuesser, WordleFeedback};
+
+/// A solver for games with several boards, like Dordle, Quordle, or Octordle.
+///
+/// Each board is tracked by its own `Solver`, and every guess is picked to minimise the total
+/// expected number of guesses across all unsolved boards.
+pub struct MultiSolver {
+    boards: Vec<Solver>,
+    solved: Vec<bool>,
+    options: Options,
+}
+
+impl Options {
+    /// Builds a solver for a game with `boards` boards.
+    pub fn build_multi(self, boards: usize) -> MultiSolver {
+        MultiSolver {
+            boards: (0..boards).map(|_| self.build()).collect(),
+            solved: vec![false; boards],
+            options: self,
+        }
+    }
+}
+
+impl MultiSolver {
+    fn pick(&self, word_idx: usize) -> String {
+        self.options.dictionary.words()[word_idx].0.to_string()
+    }
+}
+
+impl MultiGuesser for MultiSolver {
+    fn guess(&mut self, boards: &[Vec<Guess>]) -> String {
+        assert_eq!(boards.len(), self.boards.len());
+        let dictionary = self.options.dictionary;
+        for ((solver, solved), history) in self.boards.iter_mut().zip(&mut self.solved).zip(boards)
+        {
+            if *solved {
+                continue;
+            }
+            if let Some(last) = history.last() {
+                if last.mask.is_solved() {
+                    *solved = true;
+                } else {
+                    let id = dictionary.id(&last.word).unwrap_or_else(|| {
+                        panic!("guess '{}' is not in the dictionary", last.word)
+                    });
+                    solver.observe(id.index(), WordleFeedback::outcome(last.mask));
+                }
+            }
+        }
+
+        if boards.iter().all(Vec::is_empty) {
+            if let Some(first_guess) = self.options.opener() {
+                return first_guess.to_string();
+            }
+        }
+
+        let unsolved: Vec<(&Solver, usize)> = self
+            .boards
+            .iter()
+            .zip(&self.solved)
+            .zip(boards)
+            .filter(|((_, solved), _)| !**solved)
+            .map(|((solver, _), history)| (solver, history.len()))
+            .collect();
+        assert!(!unsolved.is_empty(), "all boards have been solved already");
+
+        // If we know the answer of a board, there's no point in not guessing it right away.
+        if let Some(&(solver, _)) = unsolved
+            .iter()
+            .find(|(solver, _)| solver.remaining.len() == 1)
+        {
+            let idx = solver.remaining[0].2;
+            return self.pick(idx);
+        }
+
+        // In hard mode we stick to words that could still be the answer on _some_ board. Since
+        // the dictionary is sorted by frequency, ordering by index puts the likely words first.
+        let mut candidates: Vec<usize> = unsolved
+            .iter()
+            .flat_map(|(solver, _)| solver.remaining.iter().map(|&(_, _, idx)| idx))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let initial = unsolved[0].0.initial;
+        let in_candidates: Vec<_>;
+        let mut consider = if self.options.hard_mode {
+            in_candidates = candidates.iter().map(|&idx| initial[idx]).collect();
+            &in_candidates[..]
+        } else {
+            initial
+        };
+        let stop = cutoff(candidates.len());
+        if self.options.cutoff && stop != 0 {
+            // Either way, the words to consider are in dictionary order, like the candidates.
+            let last = candidates[stop - 1];
+            consider = &consider[..consider.partition_point(|&(_, _, idx)| idx <= last)];
+        }
+
+        if self.options.rank_by == Rank::First {
+            let idx = consider[0].2;
+            return self.pick(idx);
+        }
+
+        // Every board ranks the same words, and each word is as good as it is on all of them
+        // together.
+        let mut totals = vec![0.0f64; consider.len()];
+        for &(solver, score) in &unsolved {
+            let mut total = totals.iter_mut();
+            solver.rank_words(
+                solver,
+                consider,
+                score as f64,
+                solver.remaining_entropy(),
+                |c| {
+                    *total.next().unwrap() += c.goodness;
+                },
+            );
+        }
+        let mut best: Option<Candidate> = None;
+        for (&goodness, &(_, _, idx)) in totals.iter().zip(consider) {
+            if best.is_none_or(|c| goodness > c.goodness) {
+                best = Some(Candidate { goodness, idx });
+            }
+        }
+        let best = best.unwrap();
+        self.pick(best.idx)
+    }
+}
@@ -0,0 +1,263 @@
+use super::{Candidates, Options, Prior, Rank, Solver, StepsModel};
+use crate::{Dictionary, Feedback};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// How many of the best openers by one-ply lookahead get ranked again with two-ply lookahead.
+const LOOKAHEAD_WIDTH: usize = 20;
+
+/// Everything that affects how openers are ranked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheKey {
+    /// A hash of the dictionary's words, counts, and answers.
+    dictionary: u64,
+    feedback: String,
+    sigmoid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prior: Option<Prior>,
+    rank_by: Rank,
+    #[serde(default, skip_serializing_if = "is_default")]
+    steps_model: StepsModel,
+    cutoff: bool,
+    hard_mode: bool,
+    plies: usize,
+    /// The guess limit, which only `Rank::WinProbability` looks at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_guesses: Option<usize>,
+}
+
+/// The contents of an opener cache file.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    key: CacheKey,
+    /// The best openers, best first.
+    openers: Vec<(String, f64)>,
+}
+
+impl<F: Feedback> Options<F> {
+    /// Ranks every word of the dictionary as the first guess under these options, looking
+    /// `plies` (1 or 2) guesses ahead, and returns the `top` best along with their scores.
+    ///
+    /// With one ply, words are scored the way the solver scores any guess, by `rank_by`. With two,
+    /// the best of those are scored again assuming the solver's own second guess after each
+    /// outcome: by the negated expected number of guesses for `Rank::ExpectedScore`, by the
+    /// worst case after the second guess for `Rank::Minimax`, by the probability of winning for
+    /// `Rank::WinProbability`, and by the information expected from both guesses otherwise.
+    /// Either way, higher is better.
+    ///
+    /// If `cache` is given, the ranking is read from a file in that directory if an earlier call
+    /// with the same configuration left one there, and written to it otherwise.
+    ///
+    /// Panics if `rank_by` is `Rank::First`, if `plies` isn't 1 or 2, or if `lies` isn't 0.
+    pub fn rank_openers(
+        &self,
+        plies: usize,
+        top: usize,
+        cache: Option<&Path>,
+    ) -> io::Result<Vec<(&'static str, f64)>> {
+        assert_ne!(
+            self.rank_by,
+            Rank::First,
+            "openers can't be ranked by Rank::First"
+        );
+        assert!(
+            matches!(plies, 1 | 2),
+            "openers can look 1 or 2 plies ahead"
+        );
+        assert_eq!(
+            self.lies, 0,
+            "openers can't be ranked for feedback with lies"
+        );
+        let dictionary = self.dictionary;
+        let top = top.min(dictionary.len());
+
+        let key = CacheKey {
+            dictionary: dictionary.fingerprint(),
+            feedback: std::any::type_name::<F>().to_string(),
+            sigmoid: self.sigmoid,
+            prior: self.prior.cloned(),
+            rank_by: self.rank_by,
+            steps_model: self.steps_model,
+            cutoff: self.cutoff,
+            hard_mode: self.hard_mode,
+            plies,
+            max_guesses: (self.rank_by == Rank::WinProbability).then_some(self.max_guesses),
+        };
+        let path = match cache {
+            Some(dir) => {
+                let name = serde_json::to_string(&key)?;
+                let hash = crate::fnv1a(&[name.as_bytes()]);
+                Some(dir.join(format!("opener-{:016x}.json", hash)))
+            }
+            None => None,
+        };
+        if let Some(openers) = path
+            .as_deref()
+            .and_then(|p| read_cache(p, &key, dictionary))
+        {
+            if openers.len() >= top {
+                return Ok(openers[..top].to_vec());
+            }
+        }
+
+        let solver = Options {
+            opener: None,
+            ..*self
+        }
+        .build();
+        let remaining = solver.remaining_entropy();
+        let mut totals = vec![0.0f64; F::outcomes(dictionary.word_length())];
+        let mut plogp = totals.clone();
+        let mut ranked: Vec<(f64, usize)> = solver
+            .consider()
+            .iter()
+            .map(|&(_, _, idx)| {
+                let goodness = solver.evaluate(idx, 0.0, remaining, &mut totals, &mut plogp);
+                (goodness.0, idx)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        // We keep a few more than asked for, so the cache can serve later calls that want more.
+        ranked.truncate(top.max(LOOKAHEAD_WIDTH));
+        if plies == 2 {
+            for (goodness, idx) in &mut ranked {
+                *goodness = solver.lookahead(*idx, remaining, &mut totals, &mut plogp);
+            }
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        }
+        let openers: Vec<_> = ranked
+            .into_iter()
+            .map(|(goodness, idx)| (dictionary.words()[idx].0, goodness))
+            .collect();
+
+        if let Some(path) = &path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let file = CacheFile {
+                key,
+                openers: openers
+                    .iter()
+                    .map(|&(word, goodness)| (word.to_string(), goodness))
+                    .collect(),
+            };
+            std::fs::write(path, serde_json::to_string(&file)?)?;
+        }
+        Ok(openers[..top].to_vec())
+    }
+
+    /// The same options, but opening every game with the best word by `rank_openers`.
+    pub fn with_computed_opener(self, plies: usize, cache: Option<&Path>) -> io::Result<Self> {
+        let openers = self.rank_openers(plies, 1, cache)?;
+        Ok(Self {
+            opener: openers.first().map(|&(word, _)| word),
+            ..self
+        })
+    }
+}
+
+impl<F: Feedback> Solver<F> {
+    /// How good it is to open with the word at `word_idx`, given that the solver picks the second
+    /// guess once it has seen the first one's outcome.
+    ///
+    /// `totals` and `plogp` are scratch space that must hold `F::outcomes(word_length)` elements
+    /// each.
+    fn lookahead(
+        &self,
+        word_idx: usize,
+        remaining: (f64, f64),
+        totals: &mut [f64],
+        plogp: &mut [f64],
+    ) -> f64 {
+        let length = self.options.dictionary.word_length();
+        let remaining_p = remaining.0;
+        self.fill_buckets(word_idx, totals, |p| p);
+        let outcomes: Vec<(u32, f64)> = totals
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| t != 0.0)
+            .map(|(outcome, &t)| (outcome as u32, t / remaining_p))
+            .collect();
+        let first = self.evaluate(word_idx, 0.0, remaining, totals, plogp).0;
+
+        // How good the solver's second guess is after seeing `outcome`.
+        let mut second = |outcome: u32| {
+            let mut next = self.clone();
+            next.observe(word_idx, outcome);
+            let remaining = next.remaining_entropy();
+            if next.remaining.len() == 1 {
+                next.evaluate(next.remaining[0].2, 1.0, remaining, totals, plogp)
+                    .0
+            } else {
+                next.best_guess(1.0, remaining).goodness
+            }
+        };
+        match self.options.rank_by {
+            Rank::First => unreachable!("the first candidate is picked without ranking"),
+            Rank::WinProbability => {
+                // The goodness of the second guess is the probability of winning from there.
+                outcomes
+                    .iter()
+                    .map(|&(outcome, p)| {
+                        if F::is_solved(outcome, length) {
+                            p
+                        } else {
+                            p * second(outcome)
+                        }
+                    })
+                    .sum::<f64>()
+            }
+            Rank::ExpectedScore => {
+                // The goodness of the second guess is the negated expected score of the game.
+                -outcomes
+                    .iter()
+                    .map(|&(outcome, p)| {
+                        let score = if F::is_solved(outcome, length) {
+                            1.0
+                        } else {
+                            -second(outcome)
+                        };
+                        p * score
+                    })
+                    .sum::<f64>()
+            }
+            Rank::Minimax => outcomes
+                .iter()
+                .filter(|&&(outcome, _)| !F::is_solved(outcome, length))
+                .map(|&(outcome, _)| second(outcome))
+                .fold(f64::INFINITY, f64::min),
+            Rank::WeightedInformation | Rank::InfoPlusProbability | Rank::ExpectedInformation => {
+                first
+                    + outcomes
+                        .iter()
+                        .filter(|&&(outcome, _)| !F::is_solved(outcome, length))
+                        .map(|&(outcome, p)| p * second(outcome))
+                        .sum::<f64>()
+            }
+        }
+    }
+}
+
+/// The openers cached at `path`, if there is a cache file there for `key`.
+///
+/// A cache file that can't be read, or that was written for a different configuration, is
+/// treated as missing, so that it is computed again and overwritten.
+fn read_cache(
+    path: &Path,
+    key: &CacheKey,
+    dictionary: &Dictionary,
+) -> Option<Vec<(&'static str, f64)>> {
+    let file: CacheFile = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+    if file.key != *key {
+        return None;
+    }
+    file.openers
+        .iter()
+        .map(|(word, goodness)| Some((dictionary.word(dictionary.id(word)?), *goodness)))
+        .collect()
+}
+
+fn is_default<T: Default + PartialEq>(t: &T) -> bool {
+    *t == T::default()
+}
@@ -0,0 +1,325 @@
+use super::sigmoid;
+use crate::{Dictionary, MAX_ALPHABET_SIZE};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The names of the features a `Prior` weighs, in the order `features` computes them.
+const FEATURES: [&str; 5] = [
+    // The log of the word's share of all the counts of the dictionary.
+    "log-frequency",
+    // 1 if the word ends in a single `s`, which makes it likely to be a plural.
+    "plural",
+    // 1 if the word ends in `ed`, which makes it likely to be in the past tense.
+    "past-tense",
+    // How many of the word's letters are repeats of earlier ones.
+    "repeated-letters",
+    // The average log of the share of the dictionary's words that have each of the word's letters
+    // in the same place.
+    "letter-frequency",
+];
+
+/// How strongly the fit pulls the weights of the (standardized) features towards 0, which keeps
+/// them finite when a feature tells answers apart perfectly.
+const REGULARIZATION: f64 = 1.0;
+
+/// The initial candidates of each `Dictionary` under each `Prior`, keyed by their addresses.
+static INITIAL: Lazy<Mutex<HashMap<(usize, usize), &'static [(&'static str, f64, usize)]>>> =
+    Lazy::new(Default::default);
+
+/// Every prior leaked by `Prior::leak`, so that equal priors are only leaked once.
+static LEAKED: Lazy<Mutex<Vec<&'static Prior>>> = Lazy::new(Default::default);
+
+/// A learned prior for how likely each word of a dictionary is to be the answer, which
+/// `Options::prior` uses instead of `Options::sigmoid`.
+///
+/// This is a logistic regression on a few features of each word, like how common it is and
+/// whether it looks like a plural. `Prior::fit` learns it from which words of a dictionary are
+/// answers, and it is stored as JSON, like
+///
+/// ```json
+/// {"bias":-3.1,"weights":{"letter-frequency":0.4,"log-frequency":1.6,"past-tense":-2.2, ...}}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prior {
+    bias: f64,
+    weights: BTreeMap<String, f64>,
+}
+
+impl Prior {
+    /// Learns a prior from which of the words of `dictionary` are among its answers.
+    pub fn fit(dictionary: &Dictionary) -> Self {
+        let answers: HashSet<&str> = dictionary.answers().iter().copied().collect();
+        let labels: Vec<f64> = dictionary
+            .words()
+            .iter()
+            .map(|(word, _)| f64::from(u8::from(answers.contains(word))))
+            .collect();
+        let mut xs = features(dictionary);
+        let n = xs.len() as f64;
+
+        // Newton's method converges much better on features of about the same scale, so we fit
+        // on standardized features and scale the weights back afterwards.
+        let mut means = [0.0; FEATURES.len()];
+        let mut scales = [1.0; FEATURES.len()];
+        for f in 0..FEATURES.len() {
+            means[f] = xs.iter().map(|x| x[f]).sum::<f64>() / n;
+            let var = xs.iter().map(|x| (x[f] - means[f]).powi(2)).sum::<f64>() / n;
+            if var > 0.0 {
+                scales[f] = var.sqrt();
+            }
+            for x in &mut xs {
+                x[f] = (x[f] - means[f]) / scales[f];
+            }
+        }
+
+        // The bias comes last, and isn't regularized.
+        const D: usize = FEATURES.len() + 1;
+        let mut beta = [0.0; D];
+        for _ in 0..100 {
+            let mut gradient = [0.0; D];
+            let mut hessian = [[0.0; D]; D];
+            for f in 0..FEATURES.len() {
+                gradient[f] = REGULARIZATION * beta[f];
+                hessian[f][f] = REGULARIZATION;
+            }
+            for (x, &y) in xs.iter().zip(&labels) {
+                let mut row = [1.0; D];
+                row[..FEATURES.len()].copy_from_slice(x);
+                let p = logistic(dot(&beta, &row));
+                for i in 0..D {
+                    gradient[i] += (p - y) * row[i];
+                    for j in 0..D {
+                        hessian[i][j] += p * (1.0 - p) * row[i] * row[j];
+                    }
+                }
+            }
+            let step = solve(hessian, gradient);
+            for (b, s) in beta.iter_mut().zip(&step) {
+                *b -= s;
+            }
+            if step.iter().all(|s| s.abs() < 1e-10) {
+                break;
+            }
+        }
+
+        let mut bias = beta[D - 1];
+        let mut weights = BTreeMap::new();
+        for (f, name) in FEATURES.iter().enumerate() {
+            let weight = beta[f] / scales[f];
+            bias -= weight * means[f];
+            weights.insert(name.to_string(), weight);
+        }
+        Self { bias, weights }
+    }
+
+    /// Loads a prior from a file written by [`Prior::write`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a prior in the form written by [`Prior::write`].
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let prior: Self = serde_json::from_str(text)?;
+        for name in FEATURES {
+            if !prior.weights.contains_key(name) {
+                return Err(invalid(format!("the prior has no weight for '{}'", name)));
+            }
+        }
+        if let Some(name) = prior
+            .weights
+            .keys()
+            .find(|k| !FEATURES.contains(&k.as_str()))
+        {
+            return Err(invalid(format!("'{}' is not a known feature", name)));
+        }
+        Ok(prior)
+    }
+
+    /// Leaks this prior so it can be used in `Options`, which are `Copy`.
+    ///
+    /// An equal prior that was leaked before is returned instead of leaking this one, so this is
+    /// fine to call for every game.
+    pub fn leak(self) -> &'static Self {
+        self.intern()
+    }
+
+    /// Like `leak`, but only copies this prior if no equal one was leaked before, so replaying
+    /// every game of a transcript doesn't copy its prior once per game either.
+    pub fn intern(&self) -> &'static Self {
+        let mut leaked = LEAKED.lock().unwrap();
+        if let Some(&prior) = leaked.iter().find(|&&prior| prior == self) {
+            return prior;
+        }
+        let prior = &*Box::leak(Box::new(self.clone()));
+        leaked.push(prior);
+        prior
+    }
+
+    /// Writes this prior to `w` as JSON.
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        serde_json::to_writer(&mut w, self)?;
+        writeln!(w)
+    }
+
+    /// The weight of each feature, by name, and the bias, under the name `bias`.
+    pub fn weights(&self) -> impl Iterator<Item = (&str, f64)> {
+        std::iter::once(("bias", self.bias)).chain(self.weights.iter().map(|(k, &v)| (&**k, v)))
+    }
+
+    /// The probability this prior gives every word of `dictionary` of being an answer, in
+    /// dictionary order.
+    pub fn probabilities(&self, dictionary: &Dictionary) -> Vec<f64> {
+        let weights = FEATURES.map(|name| self.weights[name]);
+        features(dictionary)
+            .iter()
+            .map(|x| logistic(self.bias + dot(&weights, x)))
+            .collect()
+    }
+
+    /// The average log loss of this prior over whether each word of `dictionary` is an answer.
+    ///
+    /// Lower is better. Compare with [`Prior::sigmoid_log_loss`].
+    pub fn log_loss(&self, dictionary: &Dictionary) -> f64 {
+        log_loss(dictionary, &self.probabilities(dictionary))
+    }
+
+    /// The average log loss of the fixed sigmoid of word counts that the solver uses when it
+    /// isn't given a prior.
+    pub fn sigmoid_log_loss(dictionary: &Dictionary) -> f64 {
+        let sum: usize = dictionary.words().iter().map(|(_, count)| count).sum();
+        let p: Vec<f64> = dictionary
+            .words()
+            .iter()
+            .map(|&(_, count)| sigmoid(count as f64 / sum as f64))
+            .collect();
+        log_loss(dictionary, &p)
+    }
+
+    /// The words of `dictionary` along with their probability under this prior and their index,
+    /// which is what a solver starts out with.
+    pub(super) fn initial(
+        &'static self,
+        dictionary: &'static Dictionary,
+    ) -> &'static [(&'static str, f64, usize)] {
+        let key = (
+            dictionary as *const Dictionary as usize,
+            self as *const Self as usize,
+        );
+        let mut initial = INITIAL.lock().unwrap();
+        initial.entry(key).or_insert_with(|| {
+            let p = self.probabilities(dictionary);
+            dictionary
+                .words()
+                .iter()
+                .zip(p)
+                .enumerate()
+                .map(|(idx, (&(word, _), p))| (word, p, idx))
+                .collect::<Vec<_>>()
+                .leak()
+        })
+    }
+}
+
+/// The value of every feature in `FEATURES` for every word of `dictionary`, in dictionary order.
+fn features(dictionary: &Dictionary) -> Vec<[f64; FEATURES.len()]> {
+    let words = dictionary.words();
+    let length = dictionary.word_length();
+    let total: f64 = words.iter().map(|&(_, count)| count as f64).sum();
+    let mut at = vec![0usize; length * MAX_ALPHABET_SIZE];
+    for idx in 0..words.len() {
+        for (i, &symbol) in dictionary.encoded(idx).iter().enumerate() {
+            at[i * MAX_ALPHABET_SIZE + usize::from(symbol)] += 1;
+        }
+    }
+    words
+        .iter()
+        .enumerate()
+        .map(|(idx, &(word, count))| {
+            let encoded = dictionary.encoded(idx);
+            let distinct = encoded
+                .iter()
+                .fold(0u64, |seen, &symbol| seen | 1 << symbol)
+                .count_ones() as usize;
+            let letters = encoded
+                .iter()
+                .enumerate()
+                .map(|(i, &symbol)| {
+                    let n = at[i * MAX_ALPHABET_SIZE + usize::from(symbol)];
+                    (n as f64 / words.len() as f64).ln()
+                })
+                .sum::<f64>()
+                / length as f64;
+            [
+                (count as f64).max(1.0).ln() - total.ln(),
+                f64::from(u8::from(word.ends_with('s') && !word.ends_with("ss"))),
+                f64::from(u8::from(word.ends_with("ed"))),
+                (length - distinct) as f64,
+                letters,
+            ]
+        })
+        .collect()
+}
+
+/// The average log loss of `p` over whether each word of `dictionary` is an answer.
+fn log_loss(dictionary: &Dictionary, p: &[f64]) -> f64 {
+    let answers: HashSet<&str> = dictionary.answers().iter().copied().collect();
+    // The sigmoid can come out as exactly 0 or 1, which would make for an infinite loss.
+    const EPSILON: f64 = 1e-9;
+    let total: f64 = dictionary
+        .words()
+        .iter()
+        .zip(p)
+        .map(|(&(word, _), &p)| {
+            let p = p.clamp(EPSILON, 1.0 - EPSILON);
+            if answers.contains(word) {
+                -p.ln()
+            } else {
+                -(1.0 - p).ln()
+            }
+        })
+        .sum();
+    total / p.len() as f64
+}
+
+fn logistic(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// The `x` for which `a x = b`, by Gaussian elimination with partial pivoting.
+///
+/// `a` is always positive definite here, thanks to the regularization, so it can be solved.
+fn solve<const D: usize>(mut a: [[f64; D]; D], mut b: [f64; D]) -> [f64; D] {
+    for col in 0..D {
+        let pivot = (col..D)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..D {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (x, p) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; D];
+    for row in (0..D).rev() {
+        let rest: f64 = (row + 1..D).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    x
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
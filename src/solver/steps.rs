@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The shape of a `StepsModel`, which is applied to a linear function of the entropy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Shape {
+    /// E[guesses] = entropy * slope + intercept
+    Linear,
+    /// E[guesses] = ln(entropy * slope + intercept)
+    Log,
+    /// E[guesses] = e^(entropy * slope + intercept)
+    Exp,
+    /// E[guesses] = 1/(entropy * slope + intercept)
+    Inverse,
+    /// E[guesses] = (entropy * slope + intercept)^2
+    Square,
+    /// E[guesses] = sqrt(entropy * slope + intercept)
+    Sqrt,
+}
+
+impl Shape {
+    /// Every shape, in the order `roget fit` tries them.
+    pub const ALL: [Shape; 6] = [
+        Shape::Linear,
+        Shape::Log,
+        Shape::Exp,
+        Shape::Inverse,
+        Shape::Square,
+        Shape::Sqrt,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Shape::Linear => "linear",
+            Shape::Log => "log",
+            Shape::Exp => "exp",
+            Shape::Inverse => "inverse",
+            Shape::Square => "square",
+            Shape::Sqrt => "sqrt",
+        }
+    }
+
+    /// The number of guesses, transformed so that it is linear in the entropy under this shape.
+    fn linearize(self, guesses: f64) -> f64 {
+        match self {
+            Shape::Linear => guesses,
+            Shape::Log => guesses.exp(),
+            Shape::Exp => guesses.ln(),
+            Shape::Inverse => 1.0 / guesses,
+            Shape::Square => guesses.sqrt(),
+            Shape::Sqrt => guesses.powi(2),
+        }
+    }
+
+    /// The inverse of `linearize`.
+    fn apply(self, linear: f64) -> f64 {
+        match self {
+            Shape::Linear => linear,
+            Shape::Log => linear.ln(),
+            Shape::Exp => linear.exp(),
+            Shape::Inverse => 1.0 / linear,
+            Shape::Square => linear.powi(2),
+            Shape::Sqrt => linear.sqrt(),
+        }
+    }
+}
+
+/// An estimate of how many more guesses it takes to find the answer when `entropy` bits of
+/// entropy are left, which `Rank::ExpectedScore` relies on.
+///
+/// Models are written as `shape:slope:intercept`, like `log:3.87:3.679`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StepsModel {
+    pub shape: Shape,
+    pub slope: f64,
+    pub intercept: f64,
+}
+
+impl StepsModel {
+    /// The estimated number of guesses left, counting the next one, when `entropy` is left.
+    pub fn estimate(&self, entropy: f64) -> f64 {
+        self.shape.apply(entropy * self.slope + self.intercept)
+    }
+
+    /// Fits a model of the given shape to `data`, which holds the entropy that was left before a
+    /// guess along with the number of guesses it then took to find the answer, counting that one.
+    ///
+    /// Like `escore-regress.r` does, this is a least-squares fit of the number of guesses,
+    /// transformed to be linear in the entropy under `shape`, on the entropy.
+    pub fn fit(shape: Shape, data: &[(f64, usize)]) -> Self {
+        let n = data.len() as f64;
+        let points = data.iter().map(|&(e, g)| (e, shape.linearize(g as f64)));
+        let (mean_e, mean_y) = points
+            .clone()
+            .fold((0.0, 0.0), |(e, y), (pe, py)| (e + pe / n, y + py / n));
+        let (cov, var) = points.fold((0.0, 0.0), |(cov, var), (e, y)| {
+            (
+                cov + (e - mean_e) * (y - mean_y),
+                var + (e - mean_e).powi(2),
+            )
+        });
+        let slope = cov / var;
+        Self {
+            shape,
+            slope,
+            intercept: mean_y - slope * mean_e,
+        }
+    }
+
+    /// Loads data to fit to from a file like `entropy.dat`, which `Solver::finish` prints with
+    /// `PRINT_ESTIMATION` set: the entropy and the number of guesses, on a line of their own each.
+    pub fn load_data(path: impl AsRef<Path>) -> io::Result<Vec<(f64, usize)>> {
+        Self::parse_data(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses data to fit to in the form `load_data` reads.
+    pub fn parse_data(text: &str) -> io::Result<Vec<(f64, usize)>> {
+        let mut data = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let point = line.split_once(' ').and_then(|(entropy, guesses)| {
+                let entropy = entropy.parse::<f64>().ok().filter(|e| e.is_finite())?;
+                let guesses = guesses.trim().parse::<usize>().ok().filter(|&g| g != 0)?;
+                Some((entropy, guesses))
+            });
+            match point {
+                Some(point) => data.push(point),
+                None => {
+                    return Err(invalid(format!(
+                        "line {} isn't an entropy and a number of guesses: '{}'",
+                        i + 1,
+                        line
+                    )))
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// The root mean square error in the number of guesses of fitting a model of the given shape
+    /// to all but one of `folds` parts of `data`, and predicting the part left out, for each part.
+    ///
+    /// Points are split into parts by their position in `data`, round-robin. The error is NaN if
+    /// a fit doesn't cover the entropy of some point it predicts, like a log of a negative
+    /// number. Panics if `folds` is 0.
+    pub fn cross_validate(shape: Shape, data: &[(f64, usize)], folds: usize) -> f64 {
+        let mut squared = 0.0;
+        for fold in 0..folds {
+            let (test, train): (Vec<_>, Vec<_>) = data
+                .iter()
+                .enumerate()
+                .partition(|&(i, _)| i % folds == fold);
+            let train: Vec<_> = train.into_iter().map(|(_, &point)| point).collect();
+            let model = Self::fit(shape, &train);
+            squared += test
+                .into_iter()
+                .map(|(_, &(e, g))| (model.estimate(e) - g as f64).powi(2))
+                .sum::<f64>();
+        }
+        (squared / data.len() as f64).sqrt()
+    }
+
+    /// This model as a formula, like `ln(entropy * 3.8700 + 3.6790)`.
+    pub fn formula(&self) -> String {
+        let linear = format!("entropy * {:.4} + {:.4}", self.slope, self.intercept);
+        match self.shape {
+            Shape::Linear => linear,
+            Shape::Log => format!("ln({})", linear),
+            Shape::Exp => format!("e^({})", linear),
+            Shape::Inverse => format!("1/({})", linear),
+            Shape::Square => format!("({})^2", linear),
+            Shape::Sqrt => format!("sqrt({})", linear),
+        }
+    }
+}
+
+impl fmt::Display for StepsModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.shape.name(), self.slope, self.intercept)
+    }
+}
+
+impl FromStr for StepsModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let [shape, slope, intercept] = parts[..] else {
+            return Err(format!("expected shape:slope:intercept, got '{}'", s));
+        };
+        let shape = Shape::ALL
+            .into_iter()
+            .find(|s| s.name() == shape)
+            .ok_or_else(|| format!("'{}' is not a known shape", shape))?;
+        let number = |x: &str| {
+            x.parse::<f64>()
+                .map_err(|e| format!("invalid number '{}': {}", x, e))
+        };
+        Ok(Self {
+            shape,
+            slope: number(slope)?,
+            intercept: number(intercept)?,
+        })
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
@@ -0,0 +1,280 @@
+use super::{cutoff, Candidate, Candidates, Options, Rank, Solver};
+use crate::{Correctness, Dictionary, IdGuess, IdGuesser, Mask, WordId, WordleFeedback};
+use std::collections::HashMap;
+
+/// The most pairs of answers the solver keeps track of at once. When more pairs fit the feedback,
+/// only the most likely ones are kept.
+const MAX_PAIRS: usize = 20_000;
+
+/// A solver for Xordle, where two answers that share no letters are hidden at once; see
+/// [`Wordle::play_xordle`](crate::Wordle::play_xordle).
+///
+/// Xordle's feedback can't be split into feedback for each answer, so the solver keeps track of
+/// the pairs of words that could be the answers. To keep their number down, it first rules out
+/// every word that couldn't be in any pair on its own, and then keeps at most `MAX_PAIRS` of the
+/// most likely pairs of the words that are left. Guesses are ranked against those pairs just like a
+/// `Solver` ranks them against its remaining candidates.
+pub struct XordleSolver {
+    /// Ranks guesses, though its own remaining candidates are never narrowed down.
+    solver: Solver,
+    options: Options,
+    /// The probability of every word of the dictionary, by index, before any guesses were made.
+    priors: Vec<f64>,
+    /// The letters of every word of the dictionary, by index, as a set of alphabet indices.
+    letters: Vec<u64>,
+    /// The words that could still be in the pair, most likely first.
+    words: Vec<usize>,
+    /// Which words of the dictionary, by index, have been guessed already.
+    guessed: Vec<bool>,
+    /// Every guess made so far, and its feedback.
+    history: Vec<(usize, Mask)>,
+}
+
+/// A pair of answers that fits the feedback so far, as positions into the list of words that
+/// appear in any pair, and its (unnormalized) probability.
+type Pair = (usize, usize, f64);
+
+impl Options {
+    /// Builds a solver for Xordle.
+    ///
+    /// Panics if `lies`, `guarantee`, or `lookahead` are set, which the solver doesn't support.
+    pub fn build_xordle(self) -> XordleSolver {
+        assert_eq!(self.lies, 0, "Xordle has no lies");
+        assert!(!self.guarantee, "the Xordle solver can't guarantee a win");
+        assert!(
+            self.lookahead.depth <= 1,
+            "the Xordle solver doesn't look ahead"
+        );
+        let dictionary = self.dictionary;
+        let solver = self.build();
+        let mut priors = vec![0.0; dictionary.len()];
+        for &(_, p, idx) in &*solver.remaining {
+            priors[idx] = p;
+        }
+        let letters = (0..dictionary.len())
+            .map(|idx| {
+                dictionary
+                    .encoded(idx)
+                    .iter()
+                    .fold(0u64, |set, &letter| set | 1 << letter)
+            })
+            .collect();
+        XordleSolver {
+            solver,
+            options: self,
+            priors,
+            letters,
+            words: (0..dictionary.len()).collect(),
+            guessed: vec![false; dictionary.len()],
+            history: Vec::new(),
+        }
+    }
+}
+
+impl XordleSolver {
+    /// Rules out every word that couldn't be one of the answers given that guessing the word at
+    /// `guess_idx` got `mask`.
+    fn observe(&mut self, guess_idx: usize, mask: Mask) {
+        self.history.push((guess_idx, mask));
+        self.guessed[guess_idx] = true;
+        let dictionary = self.options.dictionary;
+        let guess = dictionary.encoded(guess_idx);
+        // The feedback shows the better of the two answers' correctness for each letter, so
+        // neither answer can do better than the feedback anywhere. Note that an all `Correct` mask
+        // doesn't mean that the guess was an answer, since each letter may come from either one.
+        self.words.retain(|&idx| {
+            Correctness::compute_symbols(dictionary.encoded(idx), guess).merge(mask) == mask
+        });
+    }
+
+    /// The pairs that fit every guess so far, most likely first, along with the words that
+    /// appear in them.
+    fn pairs(&self) -> (Vec<usize>, Vec<Pair>) {
+        let dictionary = self.options.dictionary;
+        // The mask of every guess against every word that is left, so that checking a pair is
+        // just a matter of merging two of them.
+        let masks: Vec<Vec<Mask>> = self
+            .words
+            .iter()
+            .map(|&idx| {
+                self.history
+                    .iter()
+                    .map(|&(guess, _)| {
+                        Correctness::compute_symbols(
+                            dictionary.encoded(idx),
+                            dictionary.encoded(guess),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        let fits = |i: usize, j: usize| {
+            let (a, b) = (self.words[i], self.words[j]);
+            // The game would be over if both answers had been guessed.
+            !(self.guessed[a] && self.guessed[b])
+                && self.letters[a] & self.letters[b] == 0
+                && self
+                    .history
+                    .iter()
+                    .enumerate()
+                    .all(|(row, &(_, mask))| masks[i][row].merge(masks[j][row]) == mask)
+        };
+
+        let n = self.words.len();
+        let mut pairs = Vec::new();
+        // Walk the pairs by the sum of their words' ranks, so that if we have to stop early we
+        // have the pairs of the most likely words.
+        'sums: for sum in 1..n.saturating_sub(1) * 2 {
+            for i in sum.saturating_sub(n - 1)..=(sum - 1) / 2 {
+                let j = sum - i;
+                if fits(i, j) {
+                    pairs.push((i, j));
+                    if pairs.len() == MAX_PAIRS {
+                        break 'sums;
+                    }
+                }
+            }
+        }
+
+        // Only keep the words that are in some pair, and renumber the pairs to match.
+        let mut members = Vec::new();
+        let mut position = HashMap::new();
+        let mut member = |i: usize| {
+            *position.entry(i).or_insert_with(|| {
+                members.push(self.words[i]);
+                members.len() - 1
+            })
+        };
+        let pairs = pairs
+            .into_iter()
+            .map(|(i, j)| {
+                let p = self.priors[self.words[i]] * self.priors[self.words[j]];
+                (member(i), member(j), p)
+            })
+            .collect();
+        (members, pairs)
+    }
+}
+
+impl IdGuesser for XordleSolver {
+    fn dictionary(&self) -> &'static Dictionary {
+        self.options.dictionary
+    }
+
+    fn guess_id(&mut self, history: &[IdGuess]) -> WordId {
+        let dictionary = self.options.dictionary;
+        if let Some(last) = history.last() {
+            self.observe(last.word.index(), last.mask);
+        }
+        if history.is_empty() {
+            if let Some(first_guess) = self.options.opener() {
+                return dictionary.id(first_guess).unwrap();
+            }
+        }
+
+        let (members, pairs) = self.pairs();
+        assert!(!pairs.is_empty(), "no pair of words fits the feedback");
+        // Words of a pair that were guessed already must have been one of the answers, and so
+        // don't need to be guessed again.
+        let unguessed = |m: usize| !self.guessed[members[m]];
+        if self.options.rank_by == Rank::First || pairs.len() == 1 {
+            let (a, b, _) = pairs[0];
+            let next = if unguessed(a) { a } else { b };
+            return WordId::from_index(members[next]);
+        }
+
+        let remaining_p: f64 = pairs.iter().map(|&(_, _, p)| p).sum();
+        let remaining_entropy = -pairs
+            .iter()
+            .map(|&(_, _, p)| {
+                let p = p / remaining_p;
+                p * p.log2()
+            })
+            .sum::<f64>();
+        // How likely each word is to be an answer that is yet to be guessed.
+        let mut in_pairs: HashMap<usize, f64> = HashMap::new();
+        for &(a, b, p) in &pairs {
+            for m in [a, b] {
+                if unguessed(m) {
+                    *in_pairs.entry(members[m]).or_default() += p;
+                }
+            }
+        }
+        let mut candidates: Vec<usize> = in_pairs.keys().copied().collect();
+        candidates.sort_unstable();
+
+        // Like the candidates, the words to consider are in dictionary order, which puts likely
+        // words first.
+        let initial = self.solver.initial;
+        let mut consider: Vec<_> = if self.options.hard_mode {
+            candidates.iter().map(|&idx| initial[idx]).collect()
+        } else {
+            initial
+                .iter()
+                .copied()
+                .filter(|&(_, _, idx)| !self.guessed[idx])
+                .collect()
+        };
+        let stop = cutoff(candidates.len());
+        if self.options.cutoff && stop != 0 {
+            let last = candidates[stop - 1];
+            consider.truncate(consider.partition_point(|&(_, _, idx)| idx <= last));
+        }
+
+        let against = PairCandidates {
+            dictionary,
+            members: &members,
+            pairs: &pairs,
+            in_pairs: &in_pairs,
+        };
+        let score = history.len() as f64;
+        let mut best: Option<Candidate> = None;
+        self.solver.rank_words(
+            &against,
+            &consider,
+            score,
+            (remaining_p, remaining_entropy),
+            |c| {
+                if best.is_none_or(|b| c.goodness > b.goodness) {
+                    best = Some(c);
+                }
+            },
+        );
+        WordId::from_index(best.unwrap().idx)
+    }
+}
+
+/// The pairs that fit the feedback so far, as what guesses are ranked against.
+struct PairCandidates<'a> {
+    dictionary: &'static Dictionary,
+    /// The words that appear in any pair, by index.
+    members: &'a [usize],
+    pairs: &'a [Pair],
+    /// The (unnormalized) probability of each word, by index, that is an answer yet to be guessed.
+    in_pairs: &'a HashMap<usize, f64>,
+}
+
+impl Candidates for PairCandidates<'_> {
+    fn count(&self) -> usize {
+        self.pairs.len()
+    }
+
+    fn fill_buckets(
+        &self,
+        word_idx: usize,
+        totals: &mut [f64],
+        weigh: impl Fn(f64) -> f64,
+    ) -> Option<f64> {
+        totals.fill(0.0);
+        let guess = self.dictionary.encoded(word_idx);
+        let masks: Vec<Mask> = self
+            .members
+            .iter()
+            .map(|&member| Correctness::compute_symbols(self.dictionary.encoded(member), guess))
+            .collect();
+        for &(a, b, p) in self.pairs {
+            totals[WordleFeedback::outcome(masks[a].merge(masks[b])) as usize] += weigh(p);
+        }
+        self.in_pairs.get(&word_idx).copied()
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{
+    Dictionary, GameOutcome, Guess, IdGuess, IdGuesser, Lookahead, Mask, Options, Prior, Rank,
+    RuleViolation, StepsModel,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// A record of a single game, which can be saved and replayed later.
+///
+/// Transcripts are stored as JSON lines: one JSON object per game, on a line of its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// The answer of the game, if it is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+
+    /// The options of the solver that suggested the guesses, if a solver was involved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<RecordedOptions>,
+
+    pub turns: Vec<Turn>,
+}
+
+/// A single guess of a `Transcript`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    pub guess: String,
+
+    /// The feedback for the guess, written as `C`orrect, `M`isplaced, or `W`rong for each letter.
+    ///
+    /// Masks in any other `Notation` are accepted when reading.
+    #[serde(with = "mask_string")]
+    pub mask: Mask,
+
+    /// How many words of the dictionary were still possible answers when this guess was made.
+    pub candidates: usize,
+}
+
+/// The parts of the solver `Options` that are recorded in a `Transcript`.
+///
+/// The dictionary isn't recorded, and has to be supplied again on replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedOptions {
+    pub sigmoid: bool,
+
+    /// See `Options::prior`. The whole prior is recorded, since it is small.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prior: Option<Prior>,
+
+    pub rank_by: Rank,
+
+    /// See `Options::steps_model`. Left out when it's the default.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub steps_model: StepsModel,
+
+    pub cache: bool,
+    pub cutoff: bool,
+    pub hard_mode: bool,
+
+    /// See `Options::lies`. Left out when 0, which keeps older transcripts readable.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub lies: usize,
+
+    /// See `Options::lookahead`. Left out when it's the default.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lookahead: Lookahead,
+
+    /// See `Options::opener`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opener: Option<String>,
+
+    /// See `Options::max_guesses`. Left out when it's the default of 6.
+    #[serde(default = "six", skip_serializing_if = "is_six")]
+    pub max_guesses: usize,
+
+    /// See `Options::guarantee`. Left out when false.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub guarantee: bool,
+}
+
+impl From<Options> for RecordedOptions {
+    fn from(options: Options) -> Self {
+        Self {
+            sigmoid: options.sigmoid,
+            prior: options.prior.cloned(),
+            rank_by: options.rank_by,
+            steps_model: options.steps_model,
+            cache: options.cache,
+            cutoff: options.cutoff,
+            hard_mode: options.hard_mode,
+            lies: options.lies,
+            lookahead: options.lookahead,
+            opener: options.opener.map(str::to_string),
+            max_guesses: options.max_guesses,
+            guarantee: options.guarantee,
+        }
+    }
+}
+
+impl RecordedOptions {
+    /// The solver options these were recorded from, given the dictionary that was used.
+    ///
+    /// An opener that isn't in `dictionary` is left out.
+    pub fn with_dictionary(&self, dictionary: &'static Dictionary) -> Options {
+        Options {
+            dictionary,
+            sigmoid: self.sigmoid,
+            prior: self.prior.as_ref().map(Prior::intern),
+            rank_by: self.rank_by,
+            steps_model: self.steps_model,
+            cache: self.cache,
+            cutoff: self.cutoff,
+            hard_mode: self.hard_mode,
+            lies: self.lies,
+            lookahead: self.lookahead,
+            opener: self
+                .opener
+                .as_deref()
+                .and_then(|word| Some(dictionary.word(dictionary.id(word)?))),
+            max_guesses: self.max_guesses,
+            guarantee: self.guarantee,
+            feedback: std::marker::PhantomData,
+        }
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn six() -> usize {
+    6
+}
+
+fn is_six(n: &usize) -> bool {
+    *n == 6
+}
+
+fn is_default<T: Default + PartialEq>(t: &T) -> bool {
+    *t == T::default()
+}
+
+/// A turn at which the solver would have guessed something other than what was played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// The turn, starting at 1.
+    pub turn: usize,
+    pub played: &'static str,
+    pub suggested: &'static str,
+}
+
+impl Transcript {
+    /// Records a game given the guesses that were made, and their feedback.
+    ///
+    /// The candidate counts are computed against `dictionary`, which is also where the guesses
+    /// should come from.
+    pub fn new(
+        answer: Option<&str>,
+        history: &[Guess],
+        dictionary: &Dictionary,
+        options: Option<Options>,
+    ) -> Self {
+        let turns = (0..history.len())
+            .map(|i| Turn {
+                guess: history[i].word.to_string(),
+                mask: history[i].mask,
+                candidates: candidates(dictionary, &history[..i]),
+            })
+            .collect();
+        Self {
+            answer: answer.map(str::to_string),
+            options: options.map(RecordedOptions::from),
+            turns,
+        }
+    }
+
+    /// Records a game played with `Wordle::play` or `Wordle::play_ids`.
+    pub fn from_outcome(
+        outcome: &GameOutcome,
+        dictionary: &Dictionary,
+        options: Option<Options>,
+    ) -> Self {
+        Self::new(
+            Some(outcome.answer),
+            &outcome.transcript,
+            dictionary,
+            options,
+        )
+    }
+
+    /// Writes this transcript as a single line of JSON.
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        serde_json::to_writer(&mut w, self)?;
+        writeln!(w)
+    }
+
+    /// Reads every transcript from JSON lines, skipping blank lines.
+    pub fn read_all(r: impl BufRead) -> io::Result<Vec<Self>> {
+        let mut transcripts = Vec::new();
+        for (i, line) in r.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let transcript = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+            })?;
+            transcripts.push(transcript);
+        }
+        Ok(transcripts)
+    }
+
+    /// Feeds the guesses of this transcript to a `Solver` built from `options`, and returns every
+    /// turn at which the solver would have guessed differently.
+    ///
+    /// After a divergence the solver carries on from the guess that was actually played. The
+    /// replay stops early if the feedback rules out every word of the dictionary, since the
+    /// solver has nothing left to suggest at that point.
+    pub fn replay(&self, options: Options) -> Result<Vec<Divergence>, RuleViolation> {
+        let dictionary = options.dictionary;
+        let mut history = Vec::with_capacity(self.turns.len());
+        let mut guesses = Vec::with_capacity(self.turns.len());
+        for turn in &self.turns {
+            if turn.guess.chars().count() != dictionary.word_length() {
+                return Err(RuleViolation::WrongLength {
+                    guess: turn.guess.clone(),
+                    expected: dictionary.word_length(),
+                });
+            }
+            let word =
+                dictionary
+                    .id(&turn.guess)
+                    .ok_or_else(|| RuleViolation::NotInDictionary {
+                        guess: turn.guess.clone(),
+                    })?;
+            history.push(IdGuess {
+                word,
+                mask: turn.mask,
+            });
+            guesses.push(Guess {
+                word: dictionary.word(word).into(),
+                mask: turn.mask,
+            });
+        }
+
+        let mut solver = options.build();
+        let mut divergences = Vec::new();
+        for (i, played) in history.iter().enumerate() {
+            if candidates(dictionary, &guesses[..i]) == 0 {
+                break;
+            }
+            let suggested = solver.guess_id(&history[..i]);
+            if suggested != played.word {
+                divergences.push(Divergence {
+                    turn: i + 1,
+                    played: dictionary.word(played.word),
+                    suggested: dictionary.word(suggested),
+                });
+            }
+        }
+        Ok(divergences)
+    }
+}
+
+/// The number of words of `dictionary` that are consistent with every guess in `history`.
+fn candidates(dictionary: &Dictionary, history: &[Guess]) -> usize {
+    let history: Vec<_> = history
+        .iter()
+        .map(|guess| (guess, dictionary.alphabet().encode(&guess.word)))
+        .collect();
+    (0..dictionary.len())
+        .filter(|&idx| {
+            history.iter().all(|(guess, encoded)| match encoded {
+                Some(encoded) => {
+                    Guess::matches_symbols(encoded, &guess.mask, dictionary.encoded(idx))
+                }
+                // Letters outside the alphabet aren't in any word, so they are simply absent.
+                None => guess.matches(dictionary.words()[idx].0),
+            })
+        })
+        .count()
+}
+
+/// (De)serializes a `Mask` through its `Display` and `FromStr` impls.
+mod mask_string {
+    use crate::Mask;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(mask: &Mask, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(mask)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Mask, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse()
+            .map_err(|e| D::Error::custom(format!("invalid mask '{}': {}", s, e)))
+    }
+}
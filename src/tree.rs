@@ -0,0 +1,287 @@
+use crate::{Correctness, Dictionary, IdGuess, IdGuesser, Mask, Notation, WordId, Wordle};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// A strategy for playing every answer, as a tree of guesses.
+///
+/// A strategy can be written to a file as text in the form that published Wordle trees use, with
+/// one line per answer listing every guess along with its colors and number:
+///
+/// ```text
+/// salet BBBBB1 courd BYBBB2 nymph GGGGG3
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Strategy {
+    pub guess: WordId,
+
+    /// True if `guess` may be the answer, and so come out all `Correct`.
+    pub solves: bool,
+
+    /// The strategy to follow after each other feedback that `guess` can get, ordered by mask.
+    pub next: Vec<(Mask, Strategy)>,
+}
+
+impl Strategy {
+    /// The number of answers this strategy finds.
+    pub fn answers(&self) -> usize {
+        usize::from(self.solves) + self.next.iter().map(|(_, s)| s.answers()).sum::<usize>()
+    }
+
+    /// The total number of guesses it takes to find every answer.
+    pub fn total(&self) -> usize {
+        // Every answer found below this guess takes this guess too.
+        self.answers() + self.next.iter().map(|(_, s)| s.total()).sum::<usize>()
+    }
+
+    /// The strategy to follow after `guess` got `mask`, if there is one.
+    fn after(&self, guess: WordId, mask: Mask) -> Option<&Self> {
+        if guess != self.guess {
+            return None;
+        }
+        self.next.iter().find(|&&(m, _)| m == mask).map(|(_, s)| s)
+    }
+
+    /// Writes this strategy to `w` as text, one line per answer, sorted by the guesses on them.
+    pub fn write(&self, dictionary: &Dictionary, mut w: impl Write) -> io::Result<()> {
+        let mut lines = Vec::new();
+        self.lines(dictionary, &mut String::new(), 1, &mut lines);
+        lines.sort_unstable();
+        for line in lines {
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the line of every answer found by this strategy to `lines`, given the guesses on the
+    /// way here in `path`.
+    fn lines(
+        &self,
+        dictionary: &Dictionary,
+        path: &mut String,
+        turn: usize,
+        lines: &mut Vec<String>,
+    ) {
+        let guess = dictionary.word(self.guess);
+        let length = dictionary.word_length();
+        let prefix = path.len();
+        if self.solves {
+            let solved = Mask::all(Correctness::Correct, length);
+            lines.push(format!(
+                "{}{} {}{}",
+                path,
+                guess,
+                solved.display(Notation::Colors),
+                turn
+            ));
+        }
+        for (mask, next) in &self.next {
+            use std::fmt::Write;
+            let _ = write!(
+                path,
+                "{} {}{} ",
+                guess,
+                mask.display(Notation::Colors),
+                turn
+            );
+            next.lines(dictionary, path, turn + 1, lines);
+            path.truncate(prefix);
+        }
+    }
+
+    /// Loads a strategy from a file written by [`Strategy::write`], or any other tree in the same
+    /// form.
+    pub fn load(dictionary: &Dictionary, path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(dictionary, &std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a strategy in the form written by [`Strategy::write`].
+    ///
+    /// The colors may be in any `Notation`, and words in either case. Every line must be a game
+    /// that could actually be played, and every line must make the same guess as the others
+    /// wherever they have gotten the same feedback so far.
+    pub fn parse(dictionary: &Dictionary, text: &str) -> io::Result<Self> {
+        let mut root: Option<Self> = None;
+        for (i, line) in text.lines().enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            let at = |msg: String| invalid(format!("line {}: {}", i + 1, msg));
+            if tokens.len() % 2 == 1 {
+                return Err(at(
+                    "expected every guess to be followed by its colors".into()
+                ));
+            }
+
+            let mut turns = Vec::with_capacity(tokens.len() / 2);
+            for (turn, pair) in tokens.chunks(2).enumerate() {
+                let word = pair[0].to_lowercase();
+                let id = dictionary
+                    .id(&word)
+                    .ok_or_else(|| at(format!("'{}' is not in the dictionary", word)))?;
+                let (mask, number) = split_colors(pair[1], dictionary.word_length());
+                let mask: Mask = mask
+                    .parse()
+                    .map_err(|e| at(format!("invalid colors '{}': {}", pair[1], e)))?;
+                if mask.len() != dictionary.word_length() {
+                    return Err(at(format!("'{}' doesn't have a color per letter", pair[1])));
+                }
+                if number.parse() != Ok(turn + 1) {
+                    return Err(at(format!(
+                        "expected '{}' to end in the guess number, {}",
+                        pair[1],
+                        turn + 1
+                    )));
+                }
+                turns.push(IdGuess { word: id, mask });
+            }
+
+            // The last guess is the answer, so the colors of every guess can be checked.
+            let answer = turns.last().unwrap().word;
+            for (turn, guess) in turns.iter().enumerate() {
+                let expected = Correctness::compute_symbols(
+                    dictionary.encoded(answer.index()),
+                    dictionary.encoded(guess.word.index()),
+                );
+                if guess.mask != expected {
+                    return Err(at(format!(
+                        "guess {} would get {} if the answer were '{}'",
+                        turn + 1,
+                        expected.display(Notation::Colors),
+                        dictionary.word(answer)
+                    )));
+                }
+            }
+
+            let mut node = root.get_or_insert_with(|| Self::new(turns[0].word));
+            for (turn, guess) in turns.iter().enumerate() {
+                if node.guess != guess.word {
+                    return Err(at(format!(
+                        "guess {} is '{}', but an earlier line guesses '{}' there",
+                        turn + 1,
+                        dictionary.word(guess.word),
+                        dictionary.word(node.guess)
+                    )));
+                }
+                if guess.mask.is_solved() {
+                    node.solves = true;
+                    break;
+                }
+                let next = turns[turn + 1].word;
+                node = node.child(guess.mask, || Self::new(next));
+            }
+        }
+        root.ok_or_else(|| invalid("the tree is empty"))
+    }
+
+    fn new(guess: WordId) -> Self {
+        Self {
+            guess,
+            solves: false,
+            next: Vec::new(),
+        }
+    }
+
+    /// The strategy to follow after `mask`, which is made with `new` if there isn't one yet.
+    fn child(&mut self, mask: Mask, new: impl FnOnce() -> Self) -> &mut Self {
+        let i = match self.next.binary_search_by_key(&mask, |&(m, _)| m) {
+            Ok(i) => i,
+            Err(i) => {
+                self.next.insert(i, (mask, new()));
+                i
+            }
+        };
+        &mut self.next[i].1
+    }
+}
+
+impl Wordle {
+    /// Plays every answer with a guesser made by `new`, and returns the tree of guesses it made.
+    ///
+    /// Answers that the guesser doesn't find, or breaks the rules trying to, are left out. Panics
+    /// if the guesser makes different guesses in the same position, since such a guesser can't
+    /// be captured by a tree.
+    pub fn record_strategy<G: IdGuesser>(&self, mut new: impl FnMut() -> G) -> Strategy {
+        let mut root: Option<Strategy> = None;
+        for &answer in self.dictionary().answers() {
+            let outcome = self.play_ids(answer, new());
+            let won = outcome.score().is_some();
+            let dictionary = self.dictionary();
+            let ids: Vec<WordId> = outcome
+                .transcript
+                .iter()
+                .map(|guess| dictionary.id(&guess.word).unwrap())
+                .collect();
+            let mut node = root.get_or_insert_with(|| Strategy::new(ids[0]));
+            for (turn, guess) in outcome.transcript.iter().enumerate() {
+                assert_eq!(
+                    node.guess, ids[turn],
+                    "the guesser made different guesses in the same position"
+                );
+                if won && turn + 1 == ids.len() {
+                    node.solves = true;
+                } else if let Some(&next) = ids.get(turn + 1) {
+                    node = node.child(guess.mask, || Strategy::new(next));
+                }
+            }
+        }
+        root.expect("there is always an answer")
+    }
+}
+
+/// A guesser that plays by a `Strategy`, which makes every guess instant.
+///
+/// Panics if asked for a guess after a position the strategy doesn't cover, like after a guess
+/// that it wouldn't have made.
+pub struct TreeGuesser<'a> {
+    dictionary: &'static Dictionary,
+    strategy: &'a Strategy,
+}
+
+impl<'a> TreeGuesser<'a> {
+    pub fn new(dictionary: &'static Dictionary, strategy: &'a Strategy) -> Self {
+        Self {
+            dictionary,
+            strategy,
+        }
+    }
+}
+
+impl IdGuesser for TreeGuesser<'_> {
+    fn dictionary(&self) -> &'static Dictionary {
+        self.dictionary
+    }
+
+    fn guess_id(&mut self, history: &[IdGuess]) -> WordId {
+        let node = history.iter().try_fold(self.strategy, |node, guess| {
+            node.after(guess.word, guess.mask)
+        });
+        match node {
+            Some(node) => node.guess,
+            None => {
+                let last = history.last().unwrap();
+                panic!(
+                    "the strategy doesn't cover '{}' getting {}",
+                    self.dictionary.word(last.word),
+                    last.mask
+                )
+            }
+        }
+    }
+}
+
+/// Splits a token like `BBYGB3` into its colors and the number that follows them.
+fn split_colors(token: &str, word_length: usize) -> (&str, &str) {
+    // The digits notation is made of digits too, so the colors end after one per letter rather
+    // than where the digits start. Variation selectors don't count, since they aren't colors.
+    let end = token
+        .char_indices()
+        .filter(|&(_, c)| c != '\u{FE0F}')
+        .nth(word_length)
+        .map_or(token.len(), |(i, _)| i);
+    token.split_at(end)
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}